@@ -0,0 +1,27 @@
+#![no_std]
+#![no_main]
+
+use lib::time::SystemTime;
+use lib::*;
+
+extern crate lib;
+
+fn main(_args: &[&str]) -> isize {
+    let now = SystemTime::now();
+    if now == SystemTime::UNIX_EPOCH {
+        errln!("date: the time is unknown");
+        return 1;
+    }
+
+    let since_epoch = now.since_epoch();
+    println!(
+        "{} UTC ({}.{:09})",
+        now.date_time(),
+        since_epoch.as_secs(),
+        since_epoch.subsec_nanos()
+    );
+
+    0
+}
+
+entry!(main);
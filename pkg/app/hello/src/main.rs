@@ -5,8 +5,9 @@ use lib::*;
 
 extern crate lib;
 
-fn main() -> isize {
+fn main(args: &[&str]) -> isize {
     println!("Hello, world!!!");
+    println!("args: {:?}", args);
 
     233
 }
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+use lib::string::String;
+use lib::*;
+
+extern crate lib;
+
+fn main(_args: &[&str]) -> isize {
+    let procs = match proc_list() {
+        Ok(procs) => procs,
+        Err(errno) => {
+            errln!("ps: {}", errno);
+            return 1;
+        }
+    };
+
+    println!("  PID | PPID | Process Name     |  Ticks  |  Memory  | Status");
+    for info in procs {
+        let status = match info.status() {
+            Some(ProcessStatus::Dead) => format!("Dead ({})", info.exit_code),
+            Some(status) => format!("{:?}", status),
+            None => String::from("?"),
+        };
+        let thread = if info.flags & PROC_THREAD != 0 {
+            " (thread)"
+        } else {
+            ""
+        };
+
+        println!(
+            " #{:<3} | #{:<3} | {:16} | {:7} | {:>6}KB | {}{}",
+            info.pid,
+            info.ppid,
+            info.name(),
+            info.ticks,
+            info.memory / 1024,
+            status,
+            thread
+        );
+    }
+
+    0
+}
+
+entry!(main);
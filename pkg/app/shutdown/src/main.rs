@@ -0,0 +1,26 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+fn main(args: &[&str]) -> isize {
+    let errno = match args.get(1).copied() {
+        None => sys_power_off(0),
+        Some("-r") => sys_reboot(false),
+        Some("-w") => sys_reboot(true),
+        Some(status) => match status.parse::<u8>() {
+            Ok(status) => sys_power_off(status),
+            Err(_) => {
+                errln!("Usage: shutdown [-r | -w | <status>]");
+                return 1;
+            }
+        },
+    };
+
+    errln!("shutdown: {}", errno);
+    1
+}
+
+entry!(main);
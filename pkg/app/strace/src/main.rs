@@ -0,0 +1,84 @@
+#![no_std]
+#![no_main]
+
+use core::time::Duration;
+use lib::string::String;
+use lib::vec::Vec;
+use lib::*;
+
+extern crate lib;
+
+/// Records read back at once
+const BATCH: usize = 32;
+
+/// Delay between two reads of the trace buffer
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+fn main(args: &[&str]) -> isize {
+    if args.len() < 2 {
+        errln!("Usage: strace <app> [args...]");
+        return 1;
+    }
+
+    let pid = match sys_spawn_flags(args[1], &args[2..], None, SPAWN_TRACE) {
+        Ok(pid) => pid,
+        Err(errno) => {
+            errln!("strace: cannot spawn {}: {}", args[1], errno);
+            return 1;
+        }
+    };
+
+    let mut records = [TraceRecord::default(); BATCH];
+    loop {
+        // checked before draining, so no record of the child is left behind
+        let status = sys_try_wait_pid(pid);
+
+        while let Ok(count) = sys_trace_read(&mut records) {
+            if count == 0 {
+                break;
+            }
+            for record in &records[..count] {
+                print_record(record);
+            }
+        }
+
+        match status {
+            Ok(Some(code)) => {
+                println!("+++ #{} exited with {} +++", pid, code);
+                return 0;
+            }
+            Ok(None) => {
+                let _ = sys_sleep(POLL_INTERVAL);
+            }
+            Err(errno) => {
+                errln!("strace: cannot wait for #{}: {}", pid, errno);
+                return 1;
+            }
+        }
+    }
+}
+
+fn print_record(record: &TraceRecord) {
+    let syscall = Syscall::from(record.syscall);
+    let args = record.args[..syscall.arg_count()]
+        .iter()
+        .map(|arg| format!("{:#x}", arg))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let ret = if record.flags & TRACE_UNFINISHED != 0 {
+        String::from("?")
+    } else {
+        match Errno::decode(record.ret) {
+            Ok(value) => format!("{:#x}", value),
+            Err(errno) => format!("{}", errno),
+        }
+    };
+
+    println!(
+        "[{:>8}] #{:<3} {:?}({}) = {}",
+        record.tick, record.pid, syscall, args, ret
+    );
+}
+
+entry!(main);
@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// Bytes of a physical frame
+const FRAME_SIZE: u64 = 4096;
+
+fn main(_args: &[&str]) -> isize {
+    let info = match sys_info() {
+        Ok(info) => info,
+        Err(errno) => {
+            errln!("sysinfo: {}", errno);
+            return 1;
+        }
+    };
+
+    let secs = info.uptime_ns() / 1_000_000_000;
+    println!("Kernel    : YSOS v{}", info.version());
+    println!(
+        "Uptime    : {}:{:02}:{:02} ({} ticks)",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        info.uptime
+    );
+    println!(
+        "Memory    : {:>8} KiB / {:>8} KiB ({} / {} frames)",
+        info.frames_used * FRAME_SIZE / 1024,
+        info.frames_total * FRAME_SIZE / 1024,
+        info.frames_used,
+        info.frames_total
+    );
+    println!(
+        "Heap      : {:>8} KiB / {:>8} KiB",
+        info.heap_used / 1024,
+        info.heap_total / 1024
+    );
+    println!("Processes : {}", info.processes);
+    println!("CPUs      : {}", info.cpus);
+
+    0
+}
+
+entry!(main);
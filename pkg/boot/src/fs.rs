@@ -79,7 +79,7 @@ pub fn load_apps(bs: &BootServices) -> AppList {
     let mut buf = [0; 8];
     let cstr_path = uefi::CStr16::from_str_with_buf("\\APP\\", &mut buf).unwrap();
 
-    let mut handle = root
+    let handle = root
         .open(cstr_path, FileMode::Read, FileAttribute::empty())
         .expect("Failed to open \\APP\\ directory");
     let mut dir = match handle.into_type().expect("Failed to get directory type") {
@@ -1,5 +1,5 @@
 use crate::drivers::uart16550::SerialPort;
-use alloc::string::{String, ToString};
+use alloc::string::String;
 use core::sync::atomic::{AtomicBool, Ordering};
 use crossbeam_queue::ArrayQueue;
 use lazy_static::lazy_static;
@@ -55,7 +55,7 @@ pub fn get_line() -> String {
                     // 退格键
                     if !line.is_empty() {
                         line.pop(); // 移除字符串最后一个字符
-                        backspace(&mut *serial_port); // 正确调用backspace方法
+                        backspace(&mut serial_port); // 正确调用backspace方法
                     }
                 }
                 '\n' => break, // 换行符，结束循环
@@ -1,3 +1,4 @@
 pub mod uart16550;
 pub mod serial;
 pub mod input;
+pub mod rtc;
@@ -0,0 +1,220 @@
+use boot::BootInfo;
+use spin::Once;
+use syscall_def::TimeSpec;
+use x86_64::instructions::port::Port;
+
+use crate::interrupt::clock;
+
+const CMOS_ADDRESS: u16 = 0x70;
+const CMOS_DATA: u16 = 0x71;
+
+/// Registers of the real-time clock in the CMOS
+#[repr(u8)]
+#[derive(Clone, Copy)]
+enum Register {
+    Seconds = 0x00,
+    Minutes = 0x02,
+    Hours = 0x04,
+    Day = 0x07,
+    Month = 0x08,
+    Year = 0x09,
+    StatusA = 0x0A,
+    StatusB = 0x0B,
+    /// Not standard, but provided by QEMU and most firmwares
+    Century = 0x32,
+}
+
+/// Status A: the clock is updating its registers
+const UPDATE_IN_PROGRESS: u8 = 0x80;
+/// Status B: hours are counted from 0 to 23
+const HOUR_FORMAT_24: u8 = 0x02;
+/// Status B: values are binary instead of BCD
+const BINARY_MODE: u8 = 0x04;
+/// Set in the hours register after noon, in 12 hour mode
+const HOUR_PM: u8 = 0x80;
+
+/// Difference between the RTC and UEFI clocks above which a warning is logged
+const MAX_DRIFT_SECS: u64 = 2;
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// Date and time of the clocks, both are assumed to hold UTC as on QEMU
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl DateTime {
+    pub fn is_valid(&self) -> bool {
+        self.year >= 1970
+            && (1..=12).contains(&self.month)
+            && (1..=31).contains(&self.day)
+            && self.hour < 24
+            && self.minute < 60
+            && self.second < 60
+    }
+
+    /// Seconds since the Unix epoch
+    pub fn unix_time(&self) -> u64 {
+        let days = days_from_civil(self.year as i64, self.month as i64, self.day as i64);
+        days as u64 * 86400
+            + self.hour as u64 * 3600
+            + self.minute as u64 * 60
+            + self.second as u64
+    }
+}
+
+/// Days since 1970-01-01 of a date of the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    // years start in March, so the leap day is the last day of the year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn read_register(reg: Register) -> u8 {
+    let mut address = Port::<u8>::new(CMOS_ADDRESS);
+    let mut data = Port::<u8>::new(CMOS_DATA);
+    unsafe {
+        address.write(reg as u8);
+        data.read()
+    }
+}
+
+/// Registers holding the date, read outside of an update
+fn read_raw() -> [u8; 7] {
+    while read_register(Register::StatusA) & UPDATE_IN_PROGRESS != 0 {
+        core::hint::spin_loop();
+    }
+
+    [
+        Register::Seconds,
+        Register::Minutes,
+        Register::Hours,
+        Register::Day,
+        Register::Month,
+        Register::Year,
+        Register::Century,
+    ]
+    .map(read_register)
+}
+
+fn from_bcd(value: u8) -> u8 {
+    (value & 0x0F) + (value >> 4) * 10
+}
+
+/// Read the date and time of the CMOS real-time clock
+pub fn read() -> Option<DateTime> {
+    // an update may still start between two registers, read until stable
+    let mut raw = read_raw();
+    loop {
+        let again = read_raw();
+        if again == raw {
+            break;
+        }
+        raw = again;
+    }
+
+    let status = read_register(Register::StatusB);
+    let decode = |value: u8| {
+        if status & BINARY_MODE != 0 {
+            value
+        } else {
+            from_bcd(value)
+        }
+    };
+
+    let [second, minute, hours, day, month, year, century] = raw;
+
+    let pm = hours & HOUR_PM != 0;
+    let mut hour = decode(hours & !HOUR_PM);
+    if status & HOUR_FORMAT_24 == 0 {
+        hour = hour % 12 + if pm { 12 } else { 0 };
+    }
+
+    let century = match decode(century) {
+        century @ 19..=21 => century as u16,
+        _ => 20,
+    };
+
+    let time = DateTime {
+        year: century * 100 + decode(year) as u16,
+        month: decode(month),
+        day: decode(day),
+        hour,
+        minute: decode(minute),
+        second: decode(second),
+    };
+
+    time.is_valid().then_some(time)
+}
+
+/// Read the time of the UEFI runtime services
+fn read_uefi(boot_info: &'static BootInfo) -> Option<(DateTime, u32)> {
+    let time = unsafe { boot_info.system_table.runtime_services().get_time() }.ok()?;
+
+    let date = DateTime {
+        year: time.year(),
+        month: time.month(),
+        day: time.day(),
+        hour: time.hour(),
+        minute: time.minute(),
+        second: time.second(),
+    };
+
+    date.is_valid().then_some((date, time.nanosecond()))
+}
+
+/// Unix time in nanoseconds read at boot, with the timer tick it was read at
+static BOOT_TIME: Once<(u64, u64)> = Once::new();
+
+/// Read the wall-clock time once, it is then advanced by the timer
+pub fn init(boot_info: &'static BootInfo) {
+    let rtc = read();
+    let uefi = read_uefi(boot_info);
+
+    let nanos = match (rtc, uefi) {
+        (Some(rtc), Some((uefi, _))) => {
+            let drift = rtc.unix_time().abs_diff(uefi.unix_time());
+            if drift > MAX_DRIFT_SECS {
+                warn!("RTC and UEFI clocks differ by {}s: {:?} / {:?}", drift, rtc, uefi);
+            }
+            rtc.unix_time() * NANOS_PER_SEC
+        }
+        (Some(rtc), None) => {
+            warn!("UEFI time unavailable, trusting the RTC.");
+            rtc.unix_time() * NANOS_PER_SEC
+        }
+        (None, Some((uefi, nanos))) => {
+            warn!("RTC time is invalid, using the UEFI time.");
+            uefi.unix_time() * NANOS_PER_SEC + nanos as u64
+        }
+        (None, None) => {
+            warn!("No valid clock found, the time starts at the Unix epoch.");
+            0
+        }
+    };
+
+    BOOT_TIME.call_once(|| (nanos, clock::read_counter()));
+
+    info!("RTC Initialized: {:?}", rtc.or(uefi.map(|(time, _)| time)));
+}
+
+/// Current wall-clock time
+pub fn now() -> TimeSpec {
+    let (base, tick) = BOOT_TIME.get().copied().unwrap_or_default();
+    let nanos = base + (clock::read_counter() - tick) * clock::NANOS_PER_TICK;
+
+    TimeSpec {
+        sec: nanos / NANOS_PER_SEC,
+        nsec: nanos % NANOS_PER_SEC,
+    }
+}
@@ -71,6 +71,11 @@ impl SerialPort {
         }
     }
 
+    /// Waits until all sent bytes left the serial port.
+    pub fn flush(&mut self) {
+        unsafe { while self.line_status_port.read() & 0x40 == 0 {} }
+    }
+
     /// Receives a byte on the serial port no wait.
     pub fn receive(&mut self) -> Option<u8> {
         unsafe {
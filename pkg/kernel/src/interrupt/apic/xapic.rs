@@ -1,3 +1,4 @@
+use crate::interrupt::clock::TIMER_INIT_COUNT;
 use crate::interrupt::consts::{Interrupts, Irq};
 use bitflags::bitflags;
 use super::LocalApic;
@@ -80,7 +81,7 @@ impl LocalApic for XApic {
             self.write(0x320, lvt_timer);
             
             self.write(0x3E0, 0b1011); // set Timer Divide to 1
-            self.write(0x380, 0x20000); // set initial count to 0x20000
+            self.write(0x380, TIMER_INIT_COUNT); // set initial count
             // FIXME: Disable logical interrupt lines (LINT0, LINT1)
             self.write(0x350, 1 << 16); // set Mask
             self.write(0x360, 1 << 16); 
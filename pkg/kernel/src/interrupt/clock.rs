@@ -22,8 +22,19 @@ pub unsafe fn register_idt(idt: &mut InterruptDescriptorTable) {
 //         super::ack();
 //     });
 // }
+/// Initial count of the periodic LAPIC timer, with the divider set to 1
+pub const TIMER_INIT_COUNT: u32 = 0x20000;
+/// Length of an APIC bus cycle, QEMU runs the APIC bus at 1 GHz
+pub const APIC_BUS_CYCLE_NS: u64 = 1;
+/// Nanoseconds between two timer interrupts
+pub const NANOS_PER_TICK: u64 = TIMER_INIT_COUNT as u64 * APIC_BUS_CYCLE_NS;
+
 pub extern "C" fn clock(mut context: ProcessContext) {
+    inc_counter();
+    crate::proc::wake_sleeping(now_ns());
+    crate::proc::send_console_signals();
     crate::proc::switch(&mut context);
+    crate::proc::handle_signals(&mut context);
     super::ack();
 }
 
@@ -45,3 +56,9 @@ pub fn inc_counter() -> u64 {
     // 使用 Relaxed 顺序增加 COUNTER 的值，返回增加后的值
     COUNTER.fetch_add(1, Ordering::Relaxed) + 1
 }
+
+/// Monotonic time since the timer was started, in nanoseconds
+#[inline]
+pub fn now_ns() -> u64 {
+    read_counter() * NANOS_PER_TICK
+}
@@ -1,6 +1,8 @@
 use crate::memory::*;
+use crate::proc::ProcessContext;
 use x86_64::registers::control::Cr2;
 use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame, PageFaultErrorCode};
+use syscall_def::Signal;
 use x86_64::VirtAddr;
 
 pub unsafe fn register_idt(idt: &mut InterruptDescriptorTable) {
@@ -134,28 +136,33 @@ pub extern "x86-interrupt" fn security_exception_handler(stack_frame: InterruptS
     panic!("EXCEPTION: SECURITY, ERROR_CODE: 0x{:016x}\n\n{:#?}", error_code, stack_frame);
 }
 
-pub extern "x86-interrupt" fn page_fault_handler(
-    stack_frame: InterruptStackFrame,
-    err_code: PageFaultErrorCode,
-) {
-    // panic!(
-    //     "EXCEPTION: PAGE FAULT, ERROR_CODE: {:?}\n\nTrying to access: {:#x}\n{:#?}",
-    //     err_code,
-    //     Cr2::read().unwrap_or(VirtAddr::new_truncate(0xdeadbeef)),
-    //     stack_frame
-    // );
+pub extern "C" fn page_fault(err_code: u64, mut context: ProcessContext) {
+    let err_code = PageFaultErrorCode::from_bits_truncate(err_code);
+    let addr = Cr2::read().unwrap_or(VirtAddr::new_truncate(0xdeadbeef));
+
+    if crate::proc::handle_page_fault(addr, err_code) {
+        return;
+    }
+
+    if err_code.contains(PageFaultErrorCode::USER_MODE) {
+        warn!(
+            "Segmentation fault at {:#x}, ERROR_CODE: {:#?}",
+            addr, err_code
+        );
+        crate::proc::fault_signal(Signal::SIGSEGV, &mut context);
+        return;
+    }
+
     warn!(
         "EXCEPTION: PAGE FAULT, ERROR_CODE: {:#?}\n\nTrying to access: {:#x}\n{:#?}",
-        err_code,
-        Cr2::read().unwrap(),
-        stack_frame
+        err_code, addr, context
     );
+    crate::proc::info_cur_proc();
     panic!("Cannot handle page fault!");
-    // if !crate::proc::handle_page_fault(Cr2::read().unwrap(), err_code) {
-    //     // FIXME: print info about which process causes page fault?
-    //     crate::proc::info_cur_proc();
-    // }
 }
+
+as_handler!(page_fault, PageFaultErrorCode);
+
 // General Protection Fault (GPF) 处理函数
 pub extern "x86-interrupt" fn general_protection_fault_handler(
     stack_frame: InterruptStackFrame,
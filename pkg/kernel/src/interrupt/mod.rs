@@ -3,7 +3,7 @@ mod consts;
 pub mod clock;
 mod serial;
 mod exceptions;
-mod syscall;
+pub mod syscall;
 
 use apic::*;
 use x86::cpuid::CpuId;
@@ -29,6 +29,7 @@ lazy_static! {
 /// 初始化中断系统
 pub fn init() {
     IDT.load(); // 加载IDT到处理器
+    syscall::init();
 
     // FIXME: check and init APIC
     CpuId::new().get_feature_info().map(
@@ -4,6 +4,8 @@
 // use crate::drivers::input::{push_key, try_pop_key}; 
 // //use crate::drivers::uart16550::SerialPort;
 // use crate::drivers::serial::get_serial_for_sure;
+use crate::proc;
+use syscall_def::Signal;
 // // use crate::drivers::input::push_key;
 // use pc_keyboard::{DecodedKey, KeyCode};
 // use core::str;
@@ -43,7 +45,7 @@
 //     }
 // }
 
-use alloc::vec::{self, Vec};
+use alloc::vec;
 use pc_keyboard::DecodedKey;
 use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame};
 use crate::drivers::input::push_key; 
@@ -65,8 +67,15 @@ pub extern "x86-interrupt" fn serial_handler(_st: InterruptStackFrame) {
 /// Should be called on every interrupt
 pub fn receive() {
     let mut buf = vec::Vec::with_capacity(4);
-    while let Some(scancode) = get_serial_for_sure().receive() {
+    // the serial port is released before handling each byte
+    loop {
+        let Some(scancode) = get_serial_for_sure().receive() else {
+            break;
+        };
         match scancode {
+            // Ctrl-C and Ctrl-Z go to the foreground group instead of the input buffer
+            3 => proc::signal_foreground(Signal::SIGINT),
+            26 => proc::signal_foreground(Signal::SIGTSTP),
             127 => push_key(DecodedKey::Unicode('\x08')),
             13 => push_key(DecodedKey::Unicode('\n')),
             c => {
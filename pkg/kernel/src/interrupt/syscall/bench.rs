@@ -0,0 +1,44 @@
+use core::arch::asm;
+use core::arch::x86_64::_rdtsc;
+
+use syscall_def::Syscall;
+
+/// Round trips measured for each way into the kernel
+const ROUNDS: u64 = 1000;
+
+/// Average cycles of `f` over `ROUNDS` calls
+fn measure(f: impl Fn()) -> u64 {
+    let start = unsafe { _rdtsc() };
+    for _ in 0..ROUNDS {
+        f();
+    }
+    let end = unsafe { _rdtsc() };
+    (end - start) / ROUNDS
+}
+
+/// Compare `int 0x80` and `syscall` with `GetPid` from the kernel process
+///
+/// Must run before other processes are spawned, the kernel process is the
+/// caller of both paths.
+pub fn bench() {
+    let int80 = measure(|| unsafe {
+        asm!(
+            "int 0x80",
+            inlateout("rax") Syscall::GetPid as usize => _,
+            out("rcx") _, out("r11") _,
+        );
+    });
+
+    let syscall = measure(|| unsafe {
+        asm!(
+            "syscall",
+            inlateout("rax") Syscall::GetPid as usize => _,
+            out("rcx") _, out("r11") _,
+        );
+    });
+
+    info!(
+        "Syscall round trip: int 0x80 {} cycles, syscall {} cycles",
+        int80, syscall
+    );
+}
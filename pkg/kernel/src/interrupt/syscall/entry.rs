@@ -0,0 +1,173 @@
+use x86_64::registers::model_specific::{Efer, EferFlags, LStar, SFMask, Star};
+use x86_64::registers::rflags::RFlags;
+use x86_64::VirtAddr;
+
+use crate::memory::gdt::{get_selector, get_user_selector};
+
+/// Values used by `syscall_entry`, which has no free register to look them up
+#[repr(C)]
+struct EntryState {
+    /// Kernel stack of the running process
+    kernel_rsp: u64,
+    /// Stack pointer of the caller while the syscall runs
+    caller_rsp: u64,
+    user_cs: u64,
+    user_ss: u64,
+    kernel_cs: u64,
+    kernel_ss: u64,
+}
+
+static mut ENTRY_STATE: EntryState = EntryState {
+    kernel_rsp: 0,
+    caller_rsp: 0,
+    user_cs: 0,
+    user_ss: 0,
+    kernel_cs: 0,
+    kernel_ss: 0,
+};
+
+extern "C" {
+    fn syscall_entry();
+}
+
+// `syscall` leaves the return address in rcx and the flags in r11. The
+// entry builds the same frame as an interrupt on the kernel stack, so that
+// the registers form a `ProcessContext` for the shared `syscall` handler.
+//
+// The handler may switch to another process, `sysret` is only taken when
+// it is equivalent to `iretq`: back to user mode with rip in rcx and rflags
+// in r11, which always holds for a plain return to the caller.
+//
+// Callers in kernel mode, like the benchmark, stay on their own stack. The
+// mode is told by the return address in rcx: user code cannot run in the
+// kernel half of the address space, while its rsp could point anywhere.
+core::arch::global_asm!(
+    ".global syscall_entry",
+    "syscall_entry:",
+    "mov [rip + {state} + 8], rsp",
+    "test rcx, rcx",
+    "js 1f",
+    "mov rsp, [rip + {state}]",
+    "push qword ptr [rip + {state} + 24]",
+    "push qword ptr [rip + {state} + 8]",
+    "push r11",
+    "push qword ptr [rip + {state} + 16]",
+    "push rcx",
+    "jmp 2f",
+    "1:",
+    "and rsp, -16",
+    "push qword ptr [rip + {state} + 40]",
+    "push qword ptr [rip + {state} + 8]",
+    "push r11",
+    "push qword ptr [rip + {state} + 32]",
+    "push rcx",
+    "2:",
+    "push rbp",
+    "push rax",
+    "push rbx",
+    "push rcx",
+    "push rdx",
+    "push rsi",
+    "push rdi",
+    "push r8",
+    "push r9",
+    "push r10",
+    "push r11",
+    "push r12",
+    "push r13",
+    "push r14",
+    "push r15",
+    "call {handler}",
+    // cs and ss of the frame
+    "mov rax, [rsp + 128]",
+    "cmp rax, [rip + {state} + 16]",
+    "jne 3f",
+    "mov rax, [rsp + 152]",
+    "cmp rax, [rip + {state} + 24]",
+    "jne 3f",
+    // rip against rcx, it must also be canonical or `sysret` faults in kernel mode
+    "mov rax, [rsp + 120]",
+    "cmp rax, [rsp + 88]",
+    "jne 3f",
+    "shr rax, 47",
+    "jnz 3f",
+    // rflags against r11
+    "mov rax, [rsp + 136]",
+    "cmp rax, [rsp + 32]",
+    "jne 3f",
+    "pop r15",
+    "pop r14",
+    "pop r13",
+    "pop r12",
+    "pop r11",
+    "pop r10",
+    "pop r9",
+    "pop r8",
+    "pop rdi",
+    "pop rsi",
+    "pop rdx",
+    "pop rcx",
+    "pop rbx",
+    "pop rax",
+    "pop rbp",
+    "mov rsp, [rsp + 24]",
+    "sysretq",
+    "3:",
+    "pop r15",
+    "pop r14",
+    "pop r13",
+    "pop r12",
+    "pop r11",
+    "pop r10",
+    "pop r9",
+    "pop r8",
+    "pop rdi",
+    "pop rsi",
+    "pop rdx",
+    "pop rcx",
+    "pop rbx",
+    "pop rax",
+    "pop rbp",
+    "iretq",
+    state = sym ENTRY_STATE,
+    handler = sym super::syscall,
+);
+
+/// Program the MSRs of the `syscall` instruction
+pub fn init() {
+    let kernel = get_selector();
+    let user = get_user_selector();
+    let entry: unsafe extern "C" fn() = syscall_entry;
+
+    unsafe {
+        ENTRY_STATE.user_cs = user.user_code_selector.0 as u64;
+        ENTRY_STATE.user_ss = user.user_data_selector.0 as u64;
+        ENTRY_STATE.kernel_cs = kernel.code_selector.0 as u64;
+        ENTRY_STATE.kernel_ss = kernel.data_selector.0 as u64;
+
+        Star::write(
+            user.user_code_selector,
+            user.user_data_selector,
+            kernel.code_selector,
+            kernel.data_selector,
+        )
+        .expect("Invalid GDT layout for syscall");
+        LStar::write(VirtAddr::new(entry as usize as u64));
+        // interrupts stay disabled until the handler returns
+        SFMask::write(
+            RFlags::INTERRUPT_FLAG
+                | RFlags::DIRECTION_FLAG
+                | RFlags::TRAP_FLAG
+                | RFlags::ALIGNMENT_CHECK,
+        );
+        Efer::update(|flags| flags.insert(EferFlags::SYSTEM_CALL_EXTENSIONS));
+    }
+
+    info!("Syscall instruction enabled.");
+}
+
+/// Enter syscalls from user mode on `top`, the kernel stack of the next process
+#[inline]
+pub fn set_kernel_stack(top: VirtAddr) {
+    unsafe { ENTRY_STATE.kernel_rsp = top.as_u64() };
+}
@@ -3,11 +3,19 @@ use alloc::format;
 use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame};
 
 // NOTE: import `ysos_syscall` package as `syscall_def` in Cargo.toml
-use syscall_def::Syscall;
+use syscall_def::{Errno, Syscall};
 
+#[cfg(feature = "syscall_bench")]
+mod bench;
+mod entry;
 mod service;
+mod trace;
 use super::consts;
 
+#[cfg(feature = "syscall_bench")]
+pub use bench::bench;
+pub use entry::{init, set_kernel_stack};
+
 // FIXME: write syscall service handler in `service.rs`
 use service::*;
 
@@ -23,78 +31,176 @@ pub unsafe fn register_idt(idt: &mut InterruptDescriptorTable) {
 
 pub extern "C" fn syscall(mut context: ProcessContext) {
     x86_64::instructions::interrupts::without_interrupts(|| {
+        // the caller runs on its own kernel stack, the others can be freed
+        crate::proc::reap_deferred();
+        let record = trace::enter(&context);
         super::syscall::dispatcher(&mut context);
+        if let Some(record) = record {
+            trace::leave(record, &context);
+        }
+        crate::proc::handle_signals(&mut context);
     });
 }
 
 as_handler!(syscall);
 
+/// Syscall number and arguments, in `rdi`, `rsi`, `rdx`, `r10`, `r8` and `r9`
 #[derive(Clone, Debug)]
 pub struct SyscallArgs {
     pub syscall: Syscall,
     pub arg0: usize,
     pub arg1: usize,
     pub arg2: usize,
+    pub arg3: usize,
+    pub arg4: usize,
+    pub arg5: usize,
 }
 
 pub fn dispatcher(context: &mut ProcessContext) {
+    let regs = &context.regs;
     let args = super::syscall::SyscallArgs::new(
-        Syscall::from(context.regs.rax),
-        context.regs.rdi,
-        context.regs.rsi,
-        context.regs.rdx,
+        Syscall::from(regs.rax),
+        [regs.rdi, regs.rsi, regs.rdx, regs.r10, regs.r8, regs.r9],
     );
 
-    // NOTE: you may want to trace syscall arguments
-    debug!("{}", args);
+    // NOTE: per-process records are kept by `trace`, see `Syscall::Trace`
+    trace!("{}", args);
 
     match args.syscall {
         // fd: arg0 as u8, buf: &[u8] (ptr: arg1 as *const u8, len: arg2)
         /* FIXME: read from fd & return length */
-        Syscall::Read => context.set_rax(sys_read(&args)),
+        Syscall::Read => sys_read(&args, context),
         // fd: arg0 as u8, buf: &[u8] (ptr: arg1 as *const u8, len: arg2)
-        Syscall::Write => context.set_rax(sys_write(&args)),
+        Syscall::Write => sys_write(&args, context),
+        // path: &str (ptr: arg0 as *const u8, len: arg1) -> fd: isize
+        Syscall::Open => context.set_rax(Errno::encode(sys_open(&args))),
+        // fd: arg0 as u8 -> status: isize
+        Syscall::Close => context.set_rax(Errno::encode(sys_close(&args))),
+        // fds: arg0 as *mut [u8; 2] -> status: isize
+        Syscall::Pipe => context.set_rax(Errno::encode(sys_pipe(&args))),
+        // fd: arg0 as u8 -> new_fd: isize
+        Syscall::Dup => context.set_rax(Errno::encode(sys_dup(&args))),
+        // fd: arg0 as u8, new_fd: arg1 as u8 -> new_fd: isize
+        Syscall::Dup2 => context.set_rax(Errno::encode(sys_dup2(&args))),
+
+        // hint: arg0 as *mut u8 (0 for any), len: arg1, prot: arg2, flags: arg3 -> addr: isize
+        Syscall::Mmap => context.set_rax(Errno::encode(sys_mmap(&args))),
+        // addr: arg0 as *mut u8, len: arg1 -> status: isize
+        Syscall::Munmap => context.set_rax(Errno::encode(sys_munmap(&args))),
+        // key: arg0 as u32, size: arg1 -> status: isize
+        Syscall::ShmGet => context.set_rax(Errno::encode(sys_shm_get(&args))),
+        // key: arg0 as u32 -> status: isize
+        Syscall::ShmRemove => context.set_rax(Errno::encode(sys_shm_remove(&args))),
+        // key: arg0 as u32, hint: arg1 as *mut u8 (0 for any) -> addr: isize
+        Syscall::ShmAttach => context.set_rax(Errno::encode(sys_shm_attach(&args))),
+        // addr: arg0 as *mut u8 -> status: isize
+        Syscall::ShmDetach => context.set_rax(Errno::encode(sys_shm_detach(&args))),
+        // addr: arg0 as *mut u8 (0 to query) -> brk: usize
+        Syscall::Brk => context.set_rax(Errno::encode(sys_brk(&args))),
 
         // None -> pid: u16
         /* FIXME: get current pid */
         Syscall::GetPid => context.set_rax(sys_get_pid() as usize),
 
-        // path: &str (ptr: arg0 as *const u8, len: arg1) -> pid: u16
+        // None -> pid: u16 (0 for the child)
+        Syscall::Fork => sys_fork(context),
+        // entry: arg0 as fn(usize), stack: arg1 as *mut u8, arg: arg2 -> tid: isize
+        Syscall::ThreadCreate => context.set_rax(Errno::encode(sys_thread_create(&args))),
+        // tid: arg0 as u16, exit_code: arg1 as *mut isize (may be null) -> tid: isize
+        Syscall::ThreadJoin => sys_thread_join(&args, context),
+
+        // path: &str (ptr: arg0 as *const u8, len: arg1), args: arg2 as *const ProgramArgs,
+        // flags: arg3 (SPAWN_*) -> pid: isize
         /* FIXME: spawn process from name */
-        Syscall::Spawn => context.set_rax(spawn_process(&args)),
+        Syscall::Spawn => context.set_rax(Errno::encode(spawn_process(&args))),
+        // path: &str (ptr: arg0 as *const u8, len: arg1), args: arg2 as *const ProgramArgs
+        // -> only returns on error
+        Syscall::Exec => sys_exec(&args, context),
         // ret: arg0 as isize
         Syscall::Exit => service::exit_process(&args, context),
-        // pid: arg0 as u16 -> status: isize
-        /* FIXME: check if the process is running or get retcode */
+        // pid: arg0 as u16 (-1 for any child), exit_code: arg1 as *mut isize (may be null),
+        // options: arg2 (WNOHANG) -> pid: isize (0 while running with WNOHANG)
         Syscall::WaitPid => sys_wait_pid(&args, context),
-
+        // ns: arg0 as u64
+        Syscall::Sleep => sys_sleep(&args, context),
+        // None -> ns: u64 (monotonic)
+        Syscall::ClockGetTime => context.set_rax(sys_clock_get_time()),
+        // buf: arg0 as *mut TimeSpec (since the Unix epoch) -> status: isize
+        Syscall::Time => context.set_rax(Errno::encode(sys_time(&args))),
+
+        // pid: arg0 as u16, pgid: arg1 as u16 (0 for the caller and its pid) -> status: isize
+        Syscall::SetPgid => context.set_rax(Errno::encode(sys_set_pgid(&args))),
+        // pid: arg0 as u16 (0 for the caller) -> pgid: isize
+        Syscall::GetPgid => context.set_rax(Errno::encode(sys_get_pgid(&args))),
+        // None -> sid: isize
+        Syscall::SetSid => context.set_rax(Errno::encode(sys_set_sid())),
+        // pid: arg0 as u16 (0 for the caller) -> sid: isize
+        Syscall::GetSid => context.set_rax(Errno::encode(sys_get_sid(&args))),
+        // pgid: arg0 as u16 -> status: isize
+        Syscall::TcSetPgrp => context.set_rax(Errno::encode(sys_tc_set_pgrp(&args))),
+        // None -> pgid: u16 (0 if none)
+        Syscall::TcGetPgrp => context.set_rax(sys_tc_get_pgrp()),
+
+        // pid: arg0 as isize (negative for a group, 0 for the caller's), sig: arg1 as Signal
+        // -> status: isize
+        Syscall::Kill => context.set_rax(Errno::encode(sys_kill(&args))),
+        // sig: arg0 as Signal, handler: arg1, trampoline: arg2 -> old_handler: isize
+        Syscall::SigAction => context.set_rax(Errno::encode(sys_sigaction(&args))),
+        // None -> only returns on error
+        Syscall::SigReturn => sys_sigreturn(context),
+
+        // op: arg0 as u8 (0: new, 1: remove, 2: signal, 3: wait), key: arg1 as u32,
+        // value: arg2 -> status: isize
+        Syscall::Sem => sys_sem(&args, context),
+
+        // key: &str (ptr: arg0 as *const u8, len: arg1), buf: &mut [u8] (ptr: arg2, len: arg3)
+        // -> len: isize
+        Syscall::GetEnv => context.set_rax(Errno::encode(sys_get_env(&args))),
+        // key: &str (ptr: arg0 as *const u8, len: arg1), value: &str (ptr: arg2, len: arg3,
+        // null to remove) -> status: isize
+        Syscall::SetEnv => context.set_rax(Errno::encode(sys_set_env(&args))),
+
+        // pid: arg0 as u16 (0 for the caller), enable: arg1 as bool -> status: isize
+        Syscall::Trace => context.set_rax(Errno::encode(sys_trace(&args))),
+        // buf: arg0 as *mut TraceRecord, count: arg1 -> count: isize
+        Syscall::TraceRead => context.set_rax(Errno::encode(sys_trace_read(&args))),
+
+        // buf: arg0 as *mut ProcessInfo, len: arg1 -> total: isize
+        Syscall::ProcList => context.set_rax(Errno::encode(sys_proc_list(&args))),
+        // status: arg0 as u8 -> only returns on error
+        Syscall::PowerOff => sys_power_off(&args, context),
+        // kind: arg0 (REBOOT_COLD or REBOOT_WARM) -> only returns on error
+        Syscall::Reboot => sys_reboot(&args, context),
+        // buf: arg0 as *mut SysInfo -> status: isize
+        Syscall::SysInfo => context.set_rax(Errno::encode(sys_sys_info(&args))),
         // None
         /* FIXME: list processes */
         Syscall::Stat => print_process_list(),
+        // buf: arg0 as *mut AppInfo, len: arg1 -> total: isize
+        Syscall::AppList => context.set_rax(Errno::encode(sys_app_list(&args))),
         // None
         /* FIXME: list available apps */
         Syscall::ListApp => list_app(),
 
-        // ----------------------------------------------------
-        // NOTE: following syscall examples are implemented
-        // ----------------------------------------------------
-
-        // layout: arg0 as *const Layout -> ptr: *mut u8
-        Syscall::Allocate => context.set_rax(sys_allocate(&args)),
-        // ptr: arg0 as *mut u8
-        Syscall::Deallocate => sys_deallocate(&args),
         // Unknown
-        Syscall::Unknown => warn!("Unhandled syscall: {:x?}", context.regs.rax),
+        Syscall::Unknown => {
+            warn!("Unhandled syscall: {:x?}", context.regs.rax);
+            context.set_rax(Errno::ENOSYS.as_ret());
+        }
     }
 }
 
 impl SyscallArgs {
-    pub fn new(syscall: Syscall, arg0: usize, arg1: usize, arg2: usize) -> Self {
+    pub fn new(syscall: Syscall, args: [usize; 6]) -> Self {
+        let [arg0, arg1, arg2, arg3, arg4, arg5] = args;
         Self {
             syscall,
             arg0,
             arg1,
             arg2,
+            arg3,
+            arg4,
+            arg5,
         }
     }
 }
@@ -103,11 +209,14 @@ impl core::fmt::Display for SyscallArgs {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         write!(
             f,
-            "SYSCALL: {:<10} (0x{:016x}, 0x{:016x}, 0x{:016x})",
+            "SYSCALL: {:<10} (0x{:016x}, 0x{:016x}, 0x{:016x}, 0x{:016x}, 0x{:016x}, 0x{:016x})",
             format!("{:?}", self.syscall),
             self.arg0,
             self.arg1,
-            self.arg2
+            self.arg2,
+            self.arg3,
+            self.arg4,
+            self.arg5
         )
     }
 }
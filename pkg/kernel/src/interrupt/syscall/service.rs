@@ -1,54 +1,204 @@
-use core::alloc::Layout;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use syscall_def::{
+    AppInfo, Errno, ProcessInfo, ProgramArgs, Signal, SysInfo, TimeSpec, TraceRecord, REBOOT_COLD,
+    REBOOT_WARM, SPAWN_TRACE, WNOHANG,
+};
 
 use crate::proc;
-use crate::proc::ProcessContext;
-use crate::utils::*;
+use crate::proc::{ProcessContext, ProcessId, SigHandler, UserPtr, UserSlice};
+use x86_64::VirtAddr;
 
-use super::SyscallArgs;
+use super::{trace, SyscallArgs};
 
-pub fn spawn_process(args: &SyscallArgs) -> usize {
+/// Max length of a path or a block of program arguments copied from the user
+const ARG_MAX: usize = 0x8000;
+/// Max bytes moved by a single read or write
+const IO_MAX: usize = 0x10000;
+
+pub fn spawn_process(args: &SyscallArgs) -> Result<usize, Errno> {
     // FIXME: get app name by args
-    //       - core::str::from_utf8_unchecked
-    //       - core::slice::from_raw_parts
     // FIXME: spawn the process by name
-    // FIXME: handle spawn error, return 0 if failed
     // FIXME: return pid as usize
-    let name_ptr = args.arg0 as *const u8;
-    let name_len = args.arg1;
+    if args.arg3 & !SPAWN_TRACE != 0 {
+        return Err(Errno::EINVAL);
+    }
+
+    let name = user_str(args.arg0, args.arg1)?;
+    let (argv, envp) = program_args(args.arg2)?;
+
+    let pid = proc::spawn(&name, &argv, envp.as_deref())?;
+    // interrupts are off for the whole syscall, the child has not run yet
+    if args.arg3 & SPAWN_TRACE != 0 {
+        proc::set_traced(pid, true)?;
+    }
+
+    Ok(pid.0 as usize)
+}
+
+pub fn sys_exec(args: &SyscallArgs, context: &mut ProcessContext) {
+    // copy the name and arguments out, the caller's memory is unmapped by exec
+    let ret = user_str(args.arg0, args.arg1).and_then(|name| {
+        let (argv, envp) = program_args(args.arg2)?;
+        proc::exec(&name, &argv, envp.as_deref(), context)
+    });
+
+    if let Err(errno) = ret {
+        context.set_rax(errno.as_ret());
+    }
+}
 
-    let name = unsafe{
-        core::str::from_utf8_unchecked(core::slice::from_raw_parts(name_ptr, name_len))
+/// Copy in the UTF-8 string of `len` bytes at `ptr`
+fn user_str(ptr: usize, len: usize) -> Result<String, Errno> {
+    if len > ARG_MAX {
+        return Err(Errno::EINVAL);
+    }
+
+    let bytes = UserSlice::new(ptr, len).read_to_vec().ok_or(Errno::EFAULT)?;
+    String::from_utf8(bytes).map_err(|_| Errno::EINVAL)
+}
+
+/// Copy in the argument and environment blocks of a `ProgramArgs` at `ptr`
+///
+/// A null `ptr` gives no arguments, the environment is `None` if its block
+/// is null, and the caller's one is passed on.
+fn program_args(ptr: usize) -> Result<(Vec<String>, Option<Vec<String>>), Errno> {
+    let ptr = UserPtr::<ProgramArgs>::new(ptr);
+    if ptr.is_null() {
+        return Ok((Vec::new(), None));
+    }
+
+    let args = ptr.read().ok_or(Errno::EFAULT)?;
+    let envp = if args.envp.is_null() {
+        None
+    } else {
+        Some(str_block(args.envp as usize, args.envp_len)?)
     };
-    match proc::spawn(name) {
-        Some(pid) => pid.0 as usize,
-        None => 0, // 如果进程创建失败，返回 0
+
+    Ok((str_block(args.argv as usize, args.argv_len)?, envp))
+}
+
+/// Copy in and split a block of NUL-terminated strings
+fn str_block(ptr: usize, len: usize) -> Result<Vec<String>, Errno> {
+    if ptr == 0 || len == 0 {
+        return Ok(Vec::new());
+    }
+    if len > ARG_MAX {
+        return Err(Errno::EINVAL);
     }
-    // 0
+
+    let block = UserSlice::new(ptr, len).read_to_vec().ok_or(Errno::EFAULT)?;
+    Ok(block
+        .split(|&c| c == 0)
+        .filter(|s| !s.is_empty())
+        .map(|s| String::from_utf8_lossy(s).into_owned())
+        .collect())
+}
+
+/// Copy in an environment variable name, which must not be empty or hold `=`
+fn env_key(ptr: usize, len: usize) -> Result<String, Errno> {
+    let key = user_str(ptr, len)?;
+    if key.is_empty() || key.contains(['=', '\0']) {
+        return Err(Errno::EINVAL);
+    }
+    Ok(key)
 }
 
-pub fn sys_write(args: &SyscallArgs) -> usize {
+/// Store an exit code at `ptr` unless it is null
+fn put_status(ptr: usize, code: isize) -> Result<(), Errno> {
+    let ptr = UserPtr::<isize>::new(ptr);
+    if ptr.is_null() || ptr.write(code) {
+        Ok(())
+    } else {
+        Err(Errno::EFAULT)
+    }
+}
+
+/// Decode the optional address hint of `mmap` and `shm_attach`
+fn addr_hint(addr: usize) -> Result<Option<VirtAddr>, Errno> {
+    match addr {
+        0 => Ok(None),
+        addr => VirtAddr::try_new(addr as u64)
+            .map(Some)
+            .map_err(|_| Errno::EINVAL),
+    }
+}
+
+pub fn sys_write(args: &SyscallArgs, context: &mut ProcessContext) {
     // FIXME: get buffer and fd by args
-    //       - core::slice::from_raw_parts
-    // FIXME: call proc::write -> isize
+    // FIXME: call proc::write
     // FIXME: return the result as usize
     let fd = args.arg0 as u8;
-    let ptr = args.arg1 as *const u8;
-    let len = args.arg2;
+    let buf = UserSlice::new(args.arg1, args.arg2.min(IO_MAX));
 
-    let buf = unsafe { core::slice::from_raw_parts(ptr, len) };
+    // full pipe, the syscall is restarted once there is space
+    if proc::wait_write(fd, context) {
+        return;
+    }
+
+    let ret = buf
+        .read_to_vec()
+        .ok_or(Errno::EFAULT)
+        .and_then(|data| proc::write(fd, &data));
     // write(fd, buf) as usize
-    proc::write(fd, buf) as usize
+    context.set_rax(Errno::encode(ret));
 }
 
-pub fn sys_read(args: &SyscallArgs) -> usize {
+pub fn sys_read(args: &SyscallArgs, context: &mut ProcessContext) {
     // FIXME: just like sys_write
     let fd = args.arg0 as u8;
-    let ptr = args.arg1 as *mut u8;
-    let len = args.arg2;
+    let buf = UserSlice::new(args.arg1, args.arg2.min(IO_MAX));
+
+    // empty pipe, the syscall is restarted once there is data
+    if proc::wait_read(fd, context) {
+        return;
+    }
 
-    let buf = unsafe { core::slice::from_raw_parts_mut(ptr, len) };
+    let mut data = vec![0; buf.len()];
+    let ret = proc::read(fd, &mut data).and_then(|count| {
+        if buf.write(&data[..count]) {
+            Ok(count)
+        } else {
+            Err(Errno::EFAULT)
+        }
+    });
     // read(fd ,buf) as usize
-     proc::read(fd, buf) as usize
+    context.set_rax(Errno::encode(ret));
+}
+
+pub fn sys_open(args: &SyscallArgs) -> Result<usize, Errno> {
+    let path = user_str(args.arg0, args.arg1)?;
+    proc::open(&path).map(|fd| fd as usize)
+}
+
+pub fn sys_close(args: &SyscallArgs) -> Result<usize, Errno> {
+    if proc::close(args.arg0 as u8) {
+        Ok(0)
+    } else {
+        Err(Errno::EBADF)
+    }
+}
+
+pub fn sys_dup(args: &SyscallArgs) -> Result<usize, Errno> {
+    proc::dup(args.arg0 as u8).map(|fd| fd as usize)
+}
+
+pub fn sys_dup2(args: &SyscallArgs) -> Result<usize, Errno> {
+    proc::dup2(args.arg0 as u8, args.arg1 as u8).map(|fd| fd as usize)
+}
+
+pub fn sys_pipe(args: &SyscallArgs) -> Result<usize, Errno> {
+    let fds = UserPtr::<[u8; 2]>::new(args.arg0);
+    let (read_fd, write_fd) = proc::pipe()?;
+
+    if !fds.write([read_fd, write_fd]) {
+        proc::close(read_fd);
+        proc::close(write_fd);
+        return Err(Errno::EFAULT);
+    }
+
+    Ok(0)
 }
 
 pub fn exit_process(args: &SyscallArgs, context: &mut ProcessContext) {
@@ -58,52 +208,334 @@ pub fn exit_process(args: &SyscallArgs, context: &mut ProcessContext) {
     proc::exit(retcode, context);
 }
 
-pub fn list_process() {
-    // FIXME: list all processes
-    // let processes = proc::print_process_list();
-    // for p in processes {
-    //     println!("Process ID: {}, Name: {}", p.pid(), p.name());
-    // }
+pub fn sys_sleep(args: &SyscallArgs, context: &mut ProcessContext) {
+    proc::sleep(args.arg0 as u64, context);
+}
+
+pub fn sys_clock_get_time() -> usize {
+    crate::interrupt::clock::now_ns() as usize
+}
+
+pub fn sys_time(args: &SyscallArgs) -> Result<usize, Errno> {
+    if UserPtr::<TimeSpec>::new(args.arg0).write(crate::drivers::rtc::now()) {
+        Ok(0)
+    } else {
+        Err(Errno::EFAULT)
+    }
+}
+
+pub fn sys_sem(args: &SyscallArgs, context: &mut ProcessContext) {
+    let key = args.arg1 as u32;
+
+    // wait sets the return value itself, as it may block
+    if args.arg0 == 3 {
+        proc::sem_wait(key, context);
+        return;
+    }
+
+    let ret = match args.arg0 {
+        0 => proc::new_sem(key, args.arg2),
+        1 => proc::remove_sem(key),
+        2 => proc::sem_signal(key),
+        _ => Err(Errno::EINVAL),
+    };
+
+    context.set_rax(Errno::encode(ret.map(|_| 0)));
+}
+
+pub fn sys_thread_create(args: &SyscallArgs) -> Result<usize, Errno> {
+    let (Ok(entry), Ok(stack_top)) = (
+        VirtAddr::try_new(args.arg0 as u64),
+        VirtAddr::try_new(args.arg1 as u64),
+    ) else {
+        return Err(Errno::EINVAL);
+    };
+
+    proc::thread_create(entry, stack_top, args.arg2).map(|tid| tid.0 as usize)
 }
 
-pub fn sys_allocate(args: &SyscallArgs) -> usize {
-    let layout = unsafe { (args.arg0 as *const Layout).as_ref().unwrap() };
+pub fn sys_thread_join(args: &SyscallArgs, context: &mut ProcessContext) {
+    let tid = ProcessId(args.arg0 as u16);
+
+    // the caller is blocked if the thread is still running
+    let ret = match proc::join(tid, context) {
+        Ok(Some(code)) => put_status(args.arg1, code).map(|_| tid.0 as usize),
+        Ok(None) => return,
+        Err(errno) => Err(errno),
+    };
+
+    context.set_rax(Errno::encode(ret));
+}
+
+/// Send a signal to the process `arg0`, or to the group `-arg0` if negative
+///
+/// 0 is the group of the caller. Signaling all processes with -1 is not
+/// supported.
+pub fn sys_kill(args: &SyscallArgs) -> Result<usize, Errno> {
+    let sig = Signal::try_from(args.arg1).map_err(|_| Errno::EINVAL)?;
+
+    let target = args.arg0 as isize;
+    if target == -1 {
+        return Err(Errno::EINVAL);
+    }
+    // pids are 16 bits, larger ones cannot exist
+    let id = u16::try_from(target.unsigned_abs()).map_err(|_| Errno::ESRCH)?;
+
+    if target <= 0 {
+        let pgid = match id {
+            0 => proc::get_pgid(ProcessId(0))?,
+            id => ProcessId(id),
+        };
+        return match proc::kill_group(pgid, sig) {
+            0 => Err(Errno::ESRCH),
+            _ => Ok(0),
+        };
+    }
+
+    let pid = ProcessId(id);
+    if pid == proc::KERNEL_PID {
+        warn!("Cannot send signals to the kernel process");
+        return Err(Errno::EPERM);
+    }
 
-    if layout.size() == 0 {
-        return 0;
+    if proc::kill(pid, sig) {
+        Ok(0)
+    } else {
+        Err(Errno::ESRCH)
     }
+}
+
+pub fn sys_sigaction(args: &SyscallArgs) -> Result<usize, Errno> {
+    let sig = Signal::try_from(args.arg0).map_err(|_| Errno::EINVAL)?;
 
-    let ret = crate::memory::user::USER_ALLOCATOR
-        .lock()
-        .allocate_first_fit(*layout);
+    let handler = SigHandler::from_raw(args.arg1);
+    proc::sigaction(sig, handler, args.arg2 as u64)
+        .map(|old| old.as_raw())
+        .ok_or(Errno::EINVAL)
+}
 
-    match ret {
-        Ok(ptr) => ptr.as_ptr() as usize,
-        Err(_) => 0,
+pub fn sys_sigreturn(context: &mut ProcessContext) {
+    if !proc::sigreturn(context) {
+        context.set_rax(Errno::EINVAL.as_ret());
     }
 }
 
-pub fn sys_deallocate(args: &SyscallArgs) {
-    let layout = unsafe { (args.arg1 as *const Layout).as_ref().unwrap() };
+/// Only returns if the caller is not privileged or `status` is not an exit code
+pub fn sys_power_off(args: &SyscallArgs, context: &mut ProcessContext) {
+    if !proc::is_privileged() {
+        context.set_rax(Errno::EPERM.as_ret());
+        return;
+    }
 
-    if args.arg0 == 0 || layout.size() == 0 {
+    match u8::try_from(args.arg0) {
+        Ok(status) => crate::power_off(status),
+        Err(_) => context.set_rax(Errno::EINVAL.as_ret()),
+    }
+}
+
+/// Only returns if the caller is not privileged or the reset kind is unknown
+pub fn sys_reboot(args: &SyscallArgs, context: &mut ProcessContext) {
+    if !proc::is_privileged() {
+        context.set_rax(Errno::EPERM.as_ret());
         return;
     }
 
-    let ptr = args.arg0 as *mut u8;
+    match args.arg0 {
+        REBOOT_COLD => crate::reboot(boot::ResetType::COLD),
+        REBOOT_WARM => crate::reboot(boot::ResetType::WARM),
+        _ => context.set_rax(Errno::EINVAL.as_ret()),
+    }
+}
+
+pub fn sys_set_pgid(args: &SyscallArgs) -> Result<usize, Errno> {
+    proc::set_pgid(ProcessId(args.arg0 as u16), ProcessId(args.arg1 as u16)).map(|_| 0)
+}
+
+pub fn sys_get_pgid(args: &SyscallArgs) -> Result<usize, Errno> {
+    proc::get_pgid(ProcessId(args.arg0 as u16)).map(|pgid| pgid.0 as usize)
+}
+
+pub fn sys_set_sid() -> Result<usize, Errno> {
+    proc::set_sid().map(|sid| sid.0 as usize)
+}
+
+pub fn sys_get_sid(args: &SyscallArgs) -> Result<usize, Errno> {
+    proc::get_sid(ProcessId(args.arg0 as u16)).map(|sid| sid.0 as usize)
+}
+
+pub fn sys_tc_set_pgrp(args: &SyscallArgs) -> Result<usize, Errno> {
+    proc::set_foreground(ProcessId(args.arg0 as u16)).map(|_| 0)
+}
+
+/// The foreground group of the console, 0 if there is none
+pub fn sys_tc_get_pgrp() -> usize {
+    proc::foreground().map_or(0, |pgid| pgid.0 as usize)
+}
+
+pub fn sys_trace(args: &SyscallArgs) -> Result<usize, Errno> {
+    let pid = match args.arg0 {
+        0 => proc::get_current_pid(),
+        pid => ProcessId(pid as u16),
+    };
+
+    proc::set_traced(pid, args.arg1 != 0).map(|_| 0)
+}
+
+pub fn sys_trace_read(args: &SyscallArgs) -> Result<usize, Errno> {
+    let tracer = proc::get_current_pid();
+    let records = trace::take(tracer, args.arg1.min(trace::TRACE_CAPACITY));
+
+    if UserPtr::<TraceRecord>::new(args.arg0).write_slice(&records) {
+        Ok(records.len())
+    } else {
+        trace::untake(tracer, records);
+        Err(Errno::EFAULT)
+    }
+}
+
+pub fn sys_sys_info(args: &SyscallArgs) -> Result<usize, Errno> {
+    if UserPtr::<SysInfo>::new(args.arg0).write(proc::sys_info()) {
+        Ok(0)
+    } else {
+        Err(Errno::EFAULT)
+    }
+}
+
+/// Fill the array at `arg0` with up to `arg1` processes, returns how many exist
+pub fn sys_proc_list(args: &SyscallArgs) -> Result<usize, Errno> {
+    let infos = proc::proc_list();
+    let count = infos.len().min(args.arg1);
+
+    if UserPtr::<ProcessInfo>::new(args.arg0).write_slice(&infos[..count]) {
+        Ok(infos.len())
+    } else {
+        Err(Errno::EFAULT)
+    }
+}
+
+/// Fill the array at `arg0` with up to `arg1` apps, returns how many exist
+pub fn sys_app_list(args: &SyscallArgs) -> Result<usize, Errno> {
+    let infos = proc::app_list();
+    let count = infos.len().min(args.arg1);
+
+    if UserPtr::<AppInfo>::new(args.arg0).write_slice(&infos[..count]) {
+        Ok(infos.len())
+    } else {
+        Err(Errno::EFAULT)
+    }
+}
+
+/// Copy the value of the variable `key` to `buf` if it fits, returns its length
+pub fn sys_get_env(args: &SyscallArgs) -> Result<usize, Errno> {
+    let key = env_key(args.arg0, args.arg1)?;
+    let val = proc::env(&key).ok_or(Errno::ENOENT)?;
 
-    unsafe {
-        crate::memory::user::USER_ALLOCATOR
-            .lock()
-            .deallocate(core::ptr::NonNull::new_unchecked(ptr), *layout);
+    let buf = UserSlice::new(args.arg2, args.arg3);
+    if val.len() <= buf.len() && !buf.write(val.as_bytes()) {
+        return Err(Errno::EFAULT);
     }
+    Ok(val.len())
+}
+
+/// Set the variable `key` to the value at `arg2`, a null value removes it
+pub fn sys_set_env(args: &SyscallArgs) -> Result<usize, Errno> {
+    let key = env_key(args.arg0, args.arg1)?;
+    let val = match args.arg2 {
+        0 => None,
+        ptr => Some(user_str(ptr, args.arg3)?),
+    };
+
+    proc::set_env(&key, val.as_deref());
+    Ok(0)
+}
+
+pub fn list_process() {
+    // FIXME: list all processes
+    // let processes = proc::print_process_list();
+    // for p in processes {
+    //     println!("Process ID: {}, Name: {}", p.pid(), p.name());
+    // }
 }
 
 pub fn sys_wait_pid(args: &SyscallArgs, context: &mut ProcessContext) {
-    let pid = proc::ProcessId(args.arg0 as u16);
-    proc::wait_pid(pid, context);
+    let pid = match args.arg0 as isize {
+        -1 => None,
+        pid => Some(ProcessId(pid as u16)),
+    };
+    let nohang = args.arg2 & WNOHANG != 0;
+
+    // the caller is blocked if the children are still running
+    let ret = match proc::wait_pid(pid, nohang, context) {
+        Ok(Some((pid, code))) => put_status(args.arg1, code).map(|_| pid.0 as usize),
+        Ok(None) if nohang => Ok(0),
+        Ok(None) => return,
+        Err(errno) => Err(errno),
+    };
+
+    context.set_rax(Errno::encode(ret));
 }
 
 pub fn sys_get_pid() -> u16 {
     proc::get_current_pid().0
-}
\ No newline at end of file
+}
+
+pub fn sys_fork(context: &mut ProcessContext) {
+    proc::fork(context);
+}
+
+pub fn sys_mmap(args: &SyscallArgs) -> Result<usize, Errno> {
+    let hint = addr_hint(args.arg0)?;
+    if args.arg1 == 0 {
+        return Err(Errno::EINVAL);
+    }
+
+    // protection bits and flags do not overlap
+    proc::mmap(hint, args.arg1 as u64, args.arg2 | args.arg3)
+        .map(|addr| addr.as_u64() as usize)
+        .ok_or(Errno::ENOMEM)
+}
+
+pub fn sys_munmap(args: &SyscallArgs) -> Result<usize, Errno> {
+    let addr = VirtAddr::try_new(args.arg0 as u64).map_err(|_| Errno::EINVAL)?;
+
+    if proc::munmap(addr, args.arg1 as u64) {
+        Ok(0)
+    } else {
+        Err(Errno::EINVAL)
+    }
+}
+
+pub fn sys_shm_get(args: &SyscallArgs) -> Result<usize, Errno> {
+    proc::shm_get(args.arg0 as u32, args.arg1).map(|_| 0)
+}
+
+pub fn sys_shm_remove(args: &SyscallArgs) -> Result<usize, Errno> {
+    proc::shm_remove(args.arg0 as u32).map(|_| 0)
+}
+
+pub fn sys_shm_attach(args: &SyscallArgs) -> Result<usize, Errno> {
+    let hint = addr_hint(args.arg1)?;
+    proc::shm_attach(args.arg0 as u32, hint).map(|addr| addr.as_u64() as usize)
+}
+
+pub fn sys_shm_detach(args: &SyscallArgs) -> Result<usize, Errno> {
+    let detached = VirtAddr::try_new(args.arg0 as u64).is_ok_and(proc::shm_detach);
+
+    if detached {
+        Ok(0)
+    } else {
+        Err(Errno::EINVAL)
+    }
+}
+
+pub fn sys_brk(args: &SyscallArgs) -> Result<usize, Errno> {
+    // 0 queries the current break
+    let new_end = match args.arg0 {
+        0 => None,
+        addr => Some(VirtAddr::try_new(addr as u64).map_err(|_| Errno::EINVAL)?),
+    };
+
+    proc::brk(new_end)
+        .map(|end| end.as_u64() as usize)
+        .ok_or(Errno::ENOMEM)
+}
@@ -0,0 +1,70 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use spin::Mutex;
+use syscall_def::{TraceRecord, TRACE_UNFINISHED};
+
+use crate::proc::{self, ProcessContext, ProcessId};
+
+/// Records kept until they are read back, the oldest ones are dropped first
+pub const TRACE_CAPACITY: usize = 256;
+
+/// Syscalls of all traced processes with their tracer, in the order they were entered
+static TRACE_BUFFER: Mutex<VecDeque<(ProcessId, TraceRecord)>> = Mutex::new(VecDeque::new());
+
+/// Start a record of the syscall in `context` if the current process is traced
+pub fn enter(context: &ProcessContext) -> Option<(ProcessId, TraceRecord)> {
+    let tracer = proc::tracer()?;
+
+    let regs = &context.regs;
+    let record = TraceRecord {
+        pid: proc::get_current_pid().0,
+        syscall: regs.rax,
+        args: [regs.rdi, regs.rsi, regs.rdx, regs.r10, regs.r8, regs.r9],
+        tick: crate::interrupt::clock::read_counter(),
+        ..Default::default()
+    };
+    Some((tracer, record))
+}
+
+/// Complete `record` with the return value and store it
+///
+/// If another process is about to run, the caller blocked or exited and
+/// `context` does not hold its return value.
+pub fn leave((tracer, mut record): (ProcessId, TraceRecord), context: &ProcessContext) {
+    if proc::get_current_pid().0 == record.pid {
+        record.ret = context.regs.rax;
+    } else {
+        record.flags |= TRACE_UNFINISHED;
+    }
+
+    let mut buffer = TRACE_BUFFER.lock();
+    if buffer.len() == TRACE_CAPACITY {
+        buffer.pop_front();
+    }
+    buffer.push_back((tracer, record));
+}
+
+/// Take up to `max` of the oldest records for `tracer`
+pub fn take(tracer: ProcessId, max: usize) -> Vec<TraceRecord> {
+    let mut buffer = TRACE_BUFFER.lock();
+    let mut records = Vec::new();
+    buffer.retain(|&(owner, record)| {
+        if owner != tracer || records.len() == max {
+            return true;
+        }
+        records.push(record);
+        false
+    });
+    records
+}
+
+/// Put back records returned by `take` that could not be delivered
+pub fn untake(tracer: ProcessId, records: Vec<TraceRecord>) {
+    let mut buffer = TRACE_BUFFER.lock();
+    for record in records.into_iter().rev() {
+        if buffer.len() == TRACE_CAPACITY {
+            break;
+        }
+        buffer.push_front((tracer, record));
+    }
+}
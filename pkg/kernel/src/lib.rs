@@ -30,12 +30,26 @@ pub mod memory;
 pub mod interrupt;
 
 pub use alloc::format;
-use boot::BootInfo;
+use boot::{BootInfo, Runtime, SystemTable};
 
 pub use elf;
 pub mod proc;
 
+/// The UEFI system table of the boot info, kept to reach the runtime services
+struct RuntimeTable(&'static SystemTable<Runtime>);
+
+// SAFETY: the table is never changed, and its runtime services are only called
+// by `reset` with interrupts disabled, on the processor running the kernel
+unsafe impl Send for RuntimeTable {}
+unsafe impl Sync for RuntimeTable {}
+
+static RUNTIME_TABLE: spin::Once<RuntimeTable> = spin::Once::new();
+
+/// Port of the QEMU `isa-debug-exit` device, see `QEMU_ARGS` in the Makefile
+const QEMU_EXIT_PORT: u16 = 0xf4;
+
 pub fn init(boot_info: &'static BootInfo) {
+    RUNTIME_TABLE.call_once(|| RuntimeTable(&boot_info.system_table));
     serial::init(); // init serial output
     logger::init(); // init logger system
     memory::address::init(boot_info);
@@ -45,6 +59,7 @@ pub fn init(boot_info: &'static BootInfo) {
     interrupt::init(); // init interrupts
     memory::init(boot_info); // init memory manager
     info!("memory Enabled.");
+    rtc::init(boot_info); // read the wall-clock time
     x86_64::instructions::interrupts::enable();
     info!("Interrupts Enabled.");
 
@@ -53,11 +68,41 @@ pub fn init(boot_info: &'static BootInfo) {
 
 pub fn shutdown(boot_info: &'static BootInfo) -> ! {
     info!("YatSenOS shutting down.");
-    unsafe {
-        boot_info.system_table.runtime_services().reset(
-            boot::ResetType::SHUTDOWN,
-            boot::UefiStatus::SUCCESS,
-            None,
-        );
+    reset(&boot_info.system_table, boot::ResetType::SHUTDOWN, 0)
+}
+
+/// Power off the machine, a nonzero `status` is reported as a failure
+///
+/// Under QEMU with the `isa-debug-exit` device, a nonzero `status` ends the
+/// session with the exit code `(status << 1) | 1`.
+pub fn power_off(status: u8) -> ! {
+    info!("YatSenOS powering off with status {}.", status);
+    reset(runtime_table(), boot::ResetType::SHUTDOWN, status as u32)
+}
+
+/// Restart the machine with a cold or a warm reset
+pub fn reboot(kind: boot::ResetType) -> ! {
+    info!("YatSenOS rebooting.");
+    reset(runtime_table(), kind, 0)
+}
+
+fn runtime_table() -> &'static SystemTable<Runtime> {
+    RUNTIME_TABLE.get().expect("Kernel has not been initialized").0
+}
+
+/// Stop scheduling, flush the logs and reset through the UEFI runtime services
+fn reset(system_table: &SystemTable<Runtime>, kind: boot::ResetType, status: u32) -> ! {
+    // no more timer interrupts, so no process runs again
+    x86_64::instructions::interrupts::disable();
+    log::logger().flush();
+
+    if status != 0 {
+        unsafe { x86_64::instructions::port::Port::<u32>::new(QEMU_EXIT_PORT).write(status) };
     }
+
+    let status = match status {
+        0 => boot::UefiStatus::SUCCESS,
+        _ => boot::UefiStatus::ABORTED,
+    };
+    unsafe { system_table.runtime_services().reset(kind, status, None) }
 }
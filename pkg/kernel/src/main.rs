@@ -11,6 +11,8 @@ boot::entry_point!(kernel_main);
 
 pub fn kernel_main(boot_info: &'static boot::BootInfo) -> ! {
     ysos::init(boot_info);
+    #[cfg(feature = "syscall_bench")]
+    ysos::interrupt::syscall::bench();
     // proc::list_app();
     info!("init start");
     spawn_init();
@@ -24,5 +26,5 @@ pub fn spawn_init() -> proc::ProcessId {
 
     proc::list_app();
     info!("before res");
-    proc::spawn("hello").unwrap()
+    proc::spawn("hello", &[], None).unwrap()
 }
@@ -1,3 +1,5 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
 use boot::{MemoryMap, MemoryType};
 use x86_64::structures::paging::{FrameAllocator, FrameDeallocator, PhysFrame, Size4KiB};
 use x86_64::PhysAddr;
@@ -15,6 +17,11 @@ pub struct BootInfoFrameAllocator {
     size: usize,
     used: usize,
     frames: BootInfoFrameIter,
+    /// Frames returned by unmapped pages, reused before the memory map.
+    recycled: Vec<PhysFrame>,
+    /// Reference counts of frames shared between address spaces (copy-on-write).
+    /// Frames absent from the map are owned by a single mapping.
+    shared: BTreeMap<PhysFrame, usize>,
 }
 
 impl BootInfoFrameAllocator {
@@ -28,6 +35,8 @@ impl BootInfoFrameAllocator {
             size,
             frames: create_frame_iter(memory_map),
             used: 0,
+            recycled: Vec::new(),
+            shared: BTreeMap::new(),
         }
     }
 
@@ -38,18 +47,55 @@ impl BootInfoFrameAllocator {
     pub fn frames_total(&self) -> usize {
         self.size
     }
+
+    /// Add a reference to a frame that is mapped by one more address space.
+    pub fn share_frame(&mut self, frame: PhysFrame) {
+        *self.shared.entry(frame).or_insert(1) += 1;
+    }
+
+    /// Drop a reference to a shared frame, returns the remaining references.
+    pub fn unshare_frame(&mut self, frame: PhysFrame) -> usize {
+        let Some(refs) = self.shared.get_mut(&frame) else {
+            return 0;
+        };
+
+        *refs -= 1;
+        let refs = *refs;
+        if refs <= 1 {
+            self.shared.remove(&frame);
+        }
+        refs
+    }
+
+    /// Release a frame unmapped from an address space.
+    ///
+    /// The frame is recycled once no address space maps it anymore.
+    pub unsafe fn release_frame(&mut self, frame: PhysFrame) {
+        if self.unshare_frame(frame) == 0 {
+            self.deallocate_frame(frame);
+        }
+    }
+
+    /// Get the number of address spaces mapping a frame.
+    pub fn frame_refs(&self, frame: PhysFrame) -> usize {
+        self.shared.get(&frame).copied().unwrap_or(1)
+    }
 }
 
 unsafe impl FrameAllocator<Size4KiB> for BootInfoFrameAllocator {
     fn allocate_frame(&mut self) -> Option<PhysFrame> {
-        self.used += 1;
-        self.frames.next()
+        let frame = self.recycled.pop().or_else(|| self.frames.next());
+        if frame.is_some() {
+            self.used += 1;
+        }
+        frame
     }
 }
 
 impl FrameDeallocator<Size4KiB> for BootInfoFrameAllocator {
-    unsafe fn deallocate_frame(&mut self, _frame: PhysFrame) {
-        // TODO: deallocate frame (not for lab 2)
+    unsafe fn deallocate_frame(&mut self, frame: PhysFrame) {
+        self.used -= 1;
+        self.recycled.push(frame);
     }
 }
 
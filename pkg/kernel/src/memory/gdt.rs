@@ -93,8 +93,9 @@ lazy_static! {
         let code_selector = gdt.append(Descriptor::kernel_code_segment());  // 向GDT中追加内核代码段描述符
         let data_selector = gdt.append(Descriptor::kernel_data_segment());  // 最佳内核数据段描述符
         let tss_selector = gdt.append(Descriptor::tss_segment(&TSS));
-        let user_code_selector = gdt.append(Descriptor::user_code_segment());
+        // `sysret` expects the user data segment right before the user code segment
         let user_data_selector = gdt.append(Descriptor::user_data_segment());
+        let user_code_selector = gdt.append(Descriptor::user_code_segment());
         (
             gdt,
             KernelSelectors { // 返回初始化后的KernelSelectors
@@ -3,7 +3,6 @@ pub mod allocator;
 mod frames;
 
 pub mod gdt;
-pub mod user;
 
 pub use address::*;
 pub use frames::*;
@@ -36,7 +35,5 @@ pub fn init(boot_info: &'static boot::BootInfo) {
         ));
     }
 
-    user::init();
-
     info!("Frame Allocator initialized.");
 }
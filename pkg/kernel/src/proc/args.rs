@@ -0,0 +1,99 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::memory::PAGE_SIZE;
+
+// auxiliary vector entry types, see System V ABI
+pub const AT_NULL: u64 = 0;
+pub const AT_PAGESZ: u64 = 6;
+pub const AT_ENTRY: u64 = 9;
+
+/// Max size of the initial stack image, the rest of the page is left for the program
+pub const ARG_MAX: u64 = PAGE_SIZE / 2;
+
+/// Image of the initial user stack
+///
+/// ```text
+/// top  -> argv & envp strings
+///         AT_NULL, auxv pairs
+///         NULL, envp[..]
+///         NULL, argv[..]
+///         argc                    (16 bytes aligned)
+/// rsp  -> fake return address
+/// ```
+pub struct ProgramStack {
+    pub image: Vec<u8>,
+    pub rsp: u64,
+    pub argc: usize,
+    pub argv: u64,
+    pub envp: u64,
+}
+
+impl ProgramStack {
+    /// Lay out `argv` and `envp` below the stack `top`
+    ///
+    /// Returns `None` if the image is larger than `ARG_MAX`.
+    pub fn new(top: u64, entry: u64, argv: &[String], envp: &[String]) -> Option<Self> {
+        let strings_size: u64 = argv
+            .iter()
+            .chain(envp.iter())
+            .map(|s| s.len() as u64 + 1)
+            .sum();
+
+        let auxv = [(AT_PAGESZ, PAGE_SIZE), (AT_ENTRY, entry), (AT_NULL, 0)];
+        let words = 1 + (argv.len() + 1) + (envp.len() + 1) + auxv.len() * 2;
+
+        let strings_start = (top - strings_size) & !0xf;
+        let argc_addr = (strings_start - words as u64 * 8) & !0xf;
+        let rsp = argc_addr - 8;
+
+        if top - rsp > ARG_MAX {
+            warn!("Program arguments too large: {} bytes", top - rsp);
+            return None;
+        }
+
+        let mut image = alloc::vec![0u8; (top - rsp) as usize];
+
+        let mut write = |addr: u64, data: &[u8]| {
+            let offset = (addr - rsp) as usize;
+            image[offset..offset + data.len()].copy_from_slice(data);
+        };
+
+        // strings
+        let mut str_addr = strings_start;
+        let mut pointers = Vec::with_capacity(words);
+        pointers.push(argv.len() as u64);
+
+        for (idx, s) in argv.iter().chain(envp.iter()).enumerate() {
+            if idx == argv.len() {
+                pointers.push(0);
+            }
+            write(str_addr, s.as_bytes());
+            pointers.push(str_addr);
+            str_addr += s.len() as u64 + 1;
+        }
+
+        if envp.is_empty() {
+            pointers.push(0);
+        }
+        pointers.push(0);
+
+        for (ty, val) in auxv {
+            pointers.push(ty);
+            pointers.push(val);
+        }
+
+        // argc, argv, envp, auxv
+        for (idx, word) in pointers.iter().enumerate() {
+            write(argc_addr + idx as u64 * 8, &word.to_le_bytes());
+        }
+
+        Some(Self {
+            image,
+            rsp,
+            argc: argv.len(),
+            argv: argc_addr + 8,
+            envp: argc_addr + 8 + (argv.len() as u64 + 1) * 8,
+        })
+    }
+}
@@ -3,7 +3,7 @@ use x86_64::{registers::rflags::RFlags, structures::idt::InterruptStackFrameValu
 use x86_64::structures::gdt::SegmentSelector;
 
 use crate::memory::gdt::get_user_selector;
-use crate::{memory::gdt::get_selector, RegistersValue};
+use crate::RegistersValue;
 
 #[repr(C)]
 #[derive(Clone, Copy)]
@@ -34,6 +34,37 @@ impl ProcessContext {
         self.value.regs.rax = value;
     }
 
+    #[inline]
+    pub fn set_rdi(&mut self, value: usize) {
+        self.value.regs.rdi = value;
+    }
+
+    #[inline]
+    pub fn set_rsi(&mut self, value: usize) {
+        self.value.regs.rsi = value;
+    }
+
+    #[inline]
+    pub fn set_rdx(&mut self, value: usize) {
+        self.value.regs.rdx = value;
+    }
+
+    /// Rewind the instruction pointer to the `syscall` or `int 0x80` instruction,
+    /// both two bytes long, so that the syscall is issued again when the
+    /// process is resumed.
+    #[inline]
+    pub fn restart_syscall(&mut self) {
+        self.value.stack_frame.instruction_pointer -= 2u64;
+    }
+
+    /// Continue at `entry` on `stack_top` when returning to user mode,
+    /// the other registers are kept.
+    #[inline]
+    pub fn redirect(&mut self, entry: VirtAddr, stack_top: VirtAddr) {
+        self.value.stack_frame.instruction_pointer = entry;
+        self.value.stack_frame.stack_pointer = stack_top;
+    }
+
     #[inline]
     pub fn save(&mut self, context: &ProcessContext) {
         self.value = context.as_ref().as_ptr().read();
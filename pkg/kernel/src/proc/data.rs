@@ -1,10 +1,13 @@
-use alloc::{collections::BTreeMap, sync::Arc};
+use alloc::{collections::BTreeMap, format};
 use spin::RwLock;
 use x86_64::structures::paging::{
     page::{PageRange, PageRangeInclusive},
     Page,
 };
-use crate::{resource, ResourceSet};
+use crate::ResourceSet;
+use super::heap::Heap;
+use super::sync::SemaphoreSet;
+use super::vma::{Vma, MMAP_END, MMAP_START};
 use super::*;
 
 #[derive(Debug, Clone)]
@@ -12,6 +15,10 @@ pub struct ProcessData {
     // shared data
     pub(super) env: Arc<RwLock<BTreeMap<String, String>>>,
     pub(super) resources: Arc<RwLock<ResourceSet>>,
+    pub(super) semaphores: Arc<RwLock<SemaphoreSet>>,
+    // memory shared by the threads of a process
+    pub(super) heap: Heap,
+    pub(super) vmas: Arc<RwLock<Vec<Vma>>>,
     // process specific data
     pub(super) stack_segment: Option<PageRange>,
     pub(super) code_segments: Option<Vec<PageRangeInclusive>>,
@@ -22,6 +29,9 @@ impl Default for ProcessData {
         Self {
             env: Arc::new(RwLock::new(BTreeMap::new())),
             resources: Arc::new(RwLock::new(ResourceSet::default())),
+            semaphores: Arc::new(RwLock::new(SemaphoreSet::default())),
+            heap: Heap::default(),
+            vmas: Arc::new(RwLock::new(Vec::new())),
             stack_segment: None,
             code_segments: None,
         }
@@ -33,6 +43,36 @@ impl ProcessData {
         Self::default()
     }
 
+    /// Data for a new program spawned by this process, the environment and
+    /// the file descriptor table are copied, the open resources are shared.
+    pub fn inherit(&self) -> Self {
+        Self {
+            env: Arc::new(RwLock::new(self.env.read().clone())),
+            resources: Arc::new(RwLock::new(self.resources.read().clone())),
+            ..Self::default()
+        }
+    }
+
+    /// Data for a forked child, a copy of the environment, the file descriptor
+    /// table and the memory layout, semaphores are shared with the parent.
+    pub fn fork(&self) -> Self {
+        Self {
+            env: Arc::new(RwLock::new(self.env.read().clone())),
+            resources: Arc::new(RwLock::new(self.resources.read().clone())),
+            heap: self.heap.fork(),
+            vmas: Arc::new(RwLock::new(self.vmas.read().clone())),
+            ..self.clone()
+        }
+    }
+
+    /// Data for a thread, everything but the stack is shared with the creator
+    pub fn thread(&self) -> Self {
+        Self {
+            stack_segment: None,
+            ..self.clone()
+        }
+    }
+
     pub fn env(&self, key: &str) -> Option<String> {
         self.env.read().get(key).cloned()
     }
@@ -41,41 +81,179 @@ impl ProcessData {
         self.env.write().insert(key.into(), val.into());
     }
 
+    pub fn remove_env(&mut self, key: &str) {
+        self.env.write().remove(key);
+    }
+
+    /// The environment as `KEY=VALUE` strings, sorted by key
+    pub fn envs(&self) -> Vec<String> {
+        self.env
+            .read()
+            .iter()
+            .map(|(key, val)| format!("{}={}", key, val))
+            .collect()
+    }
+
+    /// Replace the environment with `KEY=VALUE` strings, others are skipped
+    pub fn set_envs(&mut self, envs: &[String]) {
+        *self.env.write() = envs
+            .iter()
+            .filter_map(|env| env.split_once('='))
+            .filter(|(key, _)| !key.is_empty())
+            .map(|(key, val)| (key.into(), val.into()))
+            .collect();
+    }
+
     pub fn set_stack(&mut self, start: VirtAddr, size: u64) {
         let start = Page::containing_address(start);
         self.stack_segment = Some(Page::range(start, start + size));
     }
 
-    pub fn read(&self, fd: u8, buf: &mut [u8]) -> isize {
+    pub fn open(&self, res: Resource) -> Option<u8> {
+        self.resources.write().open(res)
+    }
+
+    pub fn close(&self, fd: u8) -> bool {
+        self.resources.write().close(fd)
+    }
+
+    pub fn dup(&self, fd: u8) -> Result<u8, Errno> {
+        self.resources.write().dup(fd)
+    }
+
+    pub fn dup2(&self, fd: u8, new_fd: u8) -> Result<u8, Errno> {
+        self.resources.write().dup2(fd, new_fd)
+    }
+
+    pub fn read(&self, fd: u8, buf: &mut [u8]) -> Result<usize, Errno> {
         self.resources.read().read(fd, buf)
     }
     
-    pub fn write(&self, fd: u8, buf: &[u8]) -> isize {
+    pub fn write(&self, fd: u8, buf: &[u8]) -> Result<usize, Errno> {
         self.resources.read().write(fd, buf)
     }
     
+    /// Bytes of the code, stack, heap and mapped regions of the process
+    pub fn memory_usage(&self) -> u64 {
+        let code: u64 = self
+            .code_segments
+            .iter()
+            .flatten()
+            .map(|range| range.end - range.start + 1)
+            .sum();
+        let stack = self
+            .stack_segment
+            .map(|range| range.end - range.start)
+            .unwrap_or(0);
+        let heap = self.heap.pages();
+        let vmas: u64 = self
+            .vmas
+            .read()
+            .iter()
+            .map(|vma| vma.pages.end - vma.pages.start)
+            .sum();
+
+        (code + stack + (heap.end - heap.start) + vmas) * PAGE_SIZE
+    }
+
+    pub fn find_vma(&self, addr: VirtAddr) -> Option<Vma> {
+        self.vmas.read().iter().find(|vma| vma.contains(addr)).copied()
+    }
+
+    /// Reserve `count` pages at `hint`, or at the first free range of the mmap area
+    pub fn reserve_vma(
+        &self,
+        hint: Option<VirtAddr>,
+        count: u64,
+        bits: usize,
+        shm: Option<u32>,
+    ) -> Option<Vma> {
+        let mut vmas = self.vmas.write();
+
+        let fits = |start: Page| {
+            let addr = start.start_address().as_u64();
+            if addr < MMAP_START || addr + count * PAGE_SIZE > MMAP_END {
+                return None;
+            }
+
+            let pages = Page::range(start, start + count);
+            if vmas.iter().any(|vma| vma.overlaps(pages)) {
+                None
+            } else {
+                Some(pages)
+            }
+        };
+
+        let pages = hint
+            .map(Page::containing_address)
+            .into_iter()
+            .chain(core::iter::once(Page::containing_address(VirtAddr::new(MMAP_START))))
+            .chain(vmas.iter().map(|vma| vma.pages.end))
+            .find_map(fits)?;
+
+        let vma = Vma {
+            shm,
+            ..Vma::new(pages, bits)
+        };
+        let idx = vmas.partition_point(|v| v.pages.start < pages.start);
+        vmas.insert(idx, vma);
+
+        Some(vma)
+    }
+
+    /// Remove `pages` from the regions, returns the ranges to be unmapped
+    pub fn remove_vmas(&self, pages: PageRange) -> Vec<PageRange> {
+        let mut vmas = self.vmas.write();
+        let mut removed = Vec::new();
+        let mut kept = Vec::with_capacity(vmas.len() + 1);
+
+        for vma in vmas.drain(..) {
+            if !vma.overlaps(pages) {
+                kept.push(vma);
+                continue;
+            }
+
+            let start = vma.pages.start.max(pages.start);
+            let end = vma.pages.end.min(pages.end);
+            removed.push(Page::range(start, end));
+
+            // the rest of a partially unmapped region is kept
+            if vma.pages.start < start {
+                kept.push(Vma {
+                    pages: Page::range(vma.pages.start, start),
+                    ..vma
+                });
+            }
+            if end < vma.pages.end {
+                kept.push(Vma {
+                    pages: Page::range(end, vma.pages.end),
+                    ..vma
+                });
+            }
+        }
+
+        *vmas = kept;
+        removed
+    }
+
+    /// Remove the shared memory segment attached at `addr`
+    pub fn take_shm_vma(&self, addr: VirtAddr) -> Option<Vma> {
+        let mut vmas = self.vmas.write();
+        let idx = vmas
+            .iter()
+            .position(|vma| vma.shm.is_some() && vma.pages.start.start_address() == addr)?;
+        Some(vmas.remove(idx))
+    }
+
+    /// Whether `addr` is below the top of the stack, in the region it may grow into
     pub fn is_on_stack(&self, addr: VirtAddr) -> bool {
-        info!("enter is on stack");
         if let Some(stack_range) = self.stack_segment {
-
             let addr = addr.as_u64();
-
             let start = stack_range.start.start_address().as_u64();
-
             let end = stack_range.end.start_address().as_u64();
 
-            // Check if the address falls within the stack range
-            // if addr >= start && addr < end {
-            //     return true;
-            // }
-    
-            // Alternatively, check if the address aligns with the expected stack region
-            // using the STACK_START_MASK
-            let cur_stack_bot = start;
-            trace!("Current stack bot: {:#x}", cur_stack_bot);
-            trace!("Address to access: {:#x}", addr);
-            info!("{:#x} {:#x}", addr, cur_stack_bot);
-            addr & STACK_START_MASK == cur_stack_bot & STACK_START_MASK
+            trace!("Stack {:#x}-{:#x}, address to access: {:#x}", start, end, addr);
+            addr < end && addr & STACK_START_MASK == start & STACK_START_MASK
         } else {
             debug!("No stack segment found");
             false
@@ -0,0 +1,101 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use alloc::sync::Arc;
+use x86_64::{
+    structures::paging::{page::PageRange, Page, PageTableFlags},
+    VirtAddr,
+};
+
+use super::PageTableContext;
+use crate::memory::PAGE_SIZE;
+
+/// Start of the heap of every user process, right above the stacks
+pub const HEAP_START: u64 = 0x4000_0000_0000;
+/// Max size of the heap of a process
+pub const HEAP_MAX_SIZE: u64 = 0x1_0000_0000; // 4 GiB
+pub const HEAP_END: u64 = HEAP_START + HEAP_MAX_SIZE;
+
+/// Heap of a user process, `[HEAP_START, end)` is mapped in its page table
+#[derive(Debug, Clone)]
+pub struct Heap {
+    /// Current program break
+    end: Arc<AtomicU64>,
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Self {
+            end: Arc::new(AtomicU64::new(HEAP_START)),
+        }
+    }
+}
+
+impl Heap {
+    /// Heap of a forked child, with the same break in its own address space
+    pub fn fork(&self) -> Self {
+        Self {
+            end: Arc::new(AtomicU64::new(self.end.load(Ordering::SeqCst))),
+        }
+    }
+
+    pub fn end(&self) -> VirtAddr {
+        VirtAddr::new(self.end.load(Ordering::SeqCst))
+    }
+
+    /// Pages mapped for the current break
+    pub fn pages(&self) -> PageRange {
+        Self::range_to(self.end.load(Ordering::SeqCst))
+    }
+
+    #[inline]
+    fn range_to(end: u64) -> PageRange {
+        let start = Page::containing_address(VirtAddr::new(HEAP_START));
+        let end = Page::containing_address(VirtAddr::new(end.next_multiple_of(PAGE_SIZE)));
+        Page::range(start, end)
+    }
+
+    /// Move the program break to `new_end`, mapping or releasing pages
+    ///
+    /// Returns the current break if `new_end` is `None`, or `None` if it is
+    /// out of the heap range or the frames run out.
+    pub fn brk(&self, new_end: Option<VirtAddr>, page_table: &PageTableContext) -> Option<VirtAddr> {
+        let Some(new_end) = new_end else {
+            return Some(self.end());
+        };
+
+        let new_end = new_end.as_u64();
+        if !(HEAP_START..=HEAP_END).contains(&new_end) {
+            warn!("Heap break out of range: {:#x}", new_end);
+            return None;
+        }
+
+        let old = Self::range_to(self.end.load(Ordering::SeqCst));
+        let new = Self::range_to(new_end);
+
+        match new.end.cmp(&old.end) {
+            core::cmp::Ordering::Greater => {
+                let flags = PageTableFlags::PRESENT
+                    | PageTableFlags::WRITABLE
+                    | PageTableFlags::USER_ACCESSIBLE
+                    | PageTableFlags::NO_EXECUTE;
+
+                if !page_table.map_pages(Page::range(old.end, new.end), flags) {
+                    warn!("Failed to grow the heap to {:#x}", new_end);
+                    return None;
+                }
+            }
+            core::cmp::Ordering::Less => page_table.unmap_pages(Page::range(new.end, old.end)),
+            core::cmp::Ordering::Equal => {}
+        }
+
+        trace!("Heap break: {:#x}", new_end);
+        self.end.store(new_end, Ordering::SeqCst);
+        Some(VirtAddr::new(new_end))
+    }
+
+    /// Release all heap pages
+    pub fn clean_up(&self, page_table: &PageTableContext) {
+        page_table.unmap_pages(self.pages());
+        self.end.store(HEAP_START, Ordering::SeqCst);
+    }
+}
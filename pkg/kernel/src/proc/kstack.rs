@@ -0,0 +1,55 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use syscall_def::Errno;
+use x86_64::VirtAddr;
+
+/// Size of the kernel stack of each process
+pub const KERNEL_STACK_SIZE: usize = 0x4000;
+
+/// Kernel stack of a process, the `syscall` instruction enters the kernel on it
+pub struct KernelStack {
+    stack: Box<[u64]>,
+}
+
+impl KernelStack {
+    /// Allocate a stack, fails with ENOMEM instead of aborting when the heap is full
+    pub fn new() -> Result<Self, Errno> {
+        let mut stack = Vec::new();
+        stack
+            .try_reserve_exact(KERNEL_STACK_SIZE / 8)
+            .map_err(|_| Errno::ENOMEM)?;
+        stack.resize(KERNEL_STACK_SIZE / 8, 0);
+
+        Ok(Self {
+            stack: stack.into_boxed_slice(),
+        })
+    }
+
+    /// Top of the stack, aligned to 16 bytes
+    pub fn top(&self) -> VirtAddr {
+        let start = VirtAddr::from_ptr(self.stack.as_ptr());
+        (start + KERNEL_STACK_SIZE as u64).align_down(16u64)
+    }
+
+    /// Whether the kernel is running on this stack
+    pub fn is_active(&self) -> bool {
+        let rsp: u64;
+        unsafe {
+            core::arch::asm!("mov {}, rsp", out(reg) rsp, options(nomem, nostack, preserves_flags));
+        }
+
+        let start = self.stack.as_ptr() as u64;
+        (start..start + KERNEL_STACK_SIZE as u64).contains(&rsp)
+    }
+}
+
+impl core::fmt::Debug for KernelStack {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "KernelStack({:#x}-{:#x})",
+            self.stack.as_ptr() as u64,
+            self.top()
+        )
+    }
+}
@@ -1,10 +1,12 @@
 use super::*;
 use crate::memory::{
-    self,
     allocator::{ALLOCATOR, HEAP_SIZE},
-    get_frame_alloc_for_sure, PAGE_SIZE,
+    get_frame_alloc_for_sure,
 };
 use alloc::{collections::*, format, sync::*};
+use core::cmp::Reverse;
+use super::signal::SigAction;
+use super::sync::SemaphoreResult;
 use spin::{Mutex, RwLock};
 
 pub static PROCESS_MANAGER: spin::Once<ProcessManager> = spin::Once::new();
@@ -30,6 +32,14 @@ pub fn get_process_manager() -> &'static ProcessManager {
 pub struct ProcessManager {
     processes: RwLock<BTreeMap<ProcessId, Arc<Process>>>,
     ready_queue: Mutex<VecDeque<ProcessId>>,
+    /// Sleeping processes ordered by their deadline in nanoseconds
+    timer_queue: Mutex<BinaryHeap<Reverse<(u64, ProcessId)>>>,
+    /// Dead processes still running on their kernel stack when they were reaped
+    deferred_reap: Mutex<Vec<ProcessId>>,
+    /// Process group receiving the signals of the console
+    foreground: Mutex<Option<ProcessId>>,
+    /// Session started by the kernel on the console, its processes may power off
+    console_session: Mutex<Option<ProcessId>>,
     app_list: Option<boot::AppListRef>,
 }
 
@@ -45,6 +55,10 @@ impl ProcessManager {
         Self {
             processes: RwLock::new(processes),
             ready_queue: Mutex::new(ready_queue),
+            timer_queue: Mutex::new(BinaryHeap::new()),
+            deferred_reap: Mutex::new(Vec::new()),
+            foreground: Mutex::new(None),
+            console_session: Mutex::new(None),
             app_list: None,
         }
     }
@@ -55,15 +69,53 @@ impl ProcessManager {
     }
     // 提供外部获取应用列表
     pub fn app_list(&self) -> Option<&boot::AppList> {
-        self.app_list.as_ref().map(|app_list| &**app_list)
+        self.app_list
     }
 
-    pub fn wait_pid(&self, pid:ProcessId) -> Option<isize>{
-        if let Some(exit_code) = self.get_exit_code(pid){
-            return Some(exit_code);
+    /// Collect the exited child `pid` of the current process, or any child if `None`
+    ///
+    /// Returns the pid and exit code of the child, which is reaped. If the
+    /// matching children are all running, returns `None` with `nohang`, or
+    /// blocks the caller and restarts the syscall when a child exits.
+    pub fn wait_pid(
+        &self,
+        pid: Option<ProcessId>,
+        nohang: bool,
+        context: &mut ProcessContext,
+    ) -> Result<Option<(ProcessId, isize)>, Errno> {
+        let current = self.current();
+
+        let mut found = false;
+        let mut exited = None;
+        for child in current.read().children() {
+            let child_inner = child.read();
+            if child_inner.is_thread() || pid.is_some_and(|pid| pid != child.pid()) {
+                continue;
+            }
+
+            found = true;
+            if child_inner.status() == ProgramStatus::Dead {
+                exited = Some((child.pid(), child_inner.exit_code().unwrap_or(0)));
+                break;
+            }
         }
-        None
+
+        if !found {
+            return Err(Errno::ECHILD);
+        }
+
+        if let Some((pid, _)) = exited {
+            self.reap(pid);
+        } else if !nohang {
+            current.write().set_waiting_child(true);
+            drop(current);
+            context.restart_syscall();
+            self.block_current(context);
+        }
+
+        Ok(exited)
     }
+
     #[inline]
     pub fn push_ready(&self, pid: ProcessId) {
         self.ready_queue.lock().push_back(pid);
@@ -113,6 +165,131 @@ impl ProcessManager {
         // pid
     }
 
+    /// Block the current process and switch to the next ready one
+    pub fn block_current(&self, context: &mut ProcessContext) {
+        self.save_current(context);
+        self.current().write().block();
+        self.switch_next(context);
+    }
+
+    /// Put a blocked process back to the ready queue
+    pub fn wake_up(&self, pid: ProcessId) {
+        // the running process is never blocked, and may be locked by the caller
+        if pid == processor::get_pid() {
+            return;
+        }
+
+        if let Some(proc) = self.get_proc(&pid) {
+            let mut inner = proc.write();
+            // a stopped process only runs again when it is continued
+            if inner.status() == ProgramStatus::Blocked && !inner.is_stopped() {
+                // woken up for any reason, a restarted `wait_pid` blocks again
+                inner.set_waiting_child(false);
+                // not handed the semaphore, `sem_wait` fails with EINTR
+                if let Some(key) = inner.take_waiting_sem() {
+                    inner.semaphores.read().cancel(key, pid);
+                }
+                inner.pause();
+                drop(inner);
+                // a sleep cut short must not wake whatever it blocks on next
+                self.cancel_sleep(pid);
+                self.push_ready(pid);
+            }
+        }
+    }
+
+    pub fn new_sem(&self, key: u32, value: usize) -> Result<(), Errno> {
+        if self.current().read().semaphores.write().insert(key, value) {
+            Ok(())
+        } else {
+            Err(Errno::EEXIST)
+        }
+    }
+
+    pub fn remove_sem(&self, key: u32) -> Result<(), Errno> {
+        let waiters = self.current().read().semaphores.write().remove(key);
+        let waiters = waiters.ok_or(Errno::ENOENT)?;
+        for pid in waiters {
+            self.wake_sem_waiter(pid, Errno::ENOENT.as_ret());
+        }
+        Ok(())
+    }
+
+    /// Wake up `pid`, dequeued from a semaphore, with the result of its wait
+    fn wake_sem_waiter(&self, pid: ProcessId, ret: usize) {
+        if let Some(proc) = self.get_proc(&pid) {
+            let mut inner = proc.write();
+            inner.take_waiting_sem();
+            inner.set_return(ret);
+        }
+        self.wake_up(pid);
+    }
+
+    pub fn sem_wait(&self, key: u32, context: &mut ProcessContext) {
+        let pid = processor::get_pid();
+        let ret = self.current().read().semaphores.read().wait(key, pid);
+
+        match ret {
+            SemaphoreResult::Ok => context.set_rax(0),
+            SemaphoreResult::NotExist => context.set_rax(Errno::ENOENT.as_ret()),
+            SemaphoreResult::Block(_) => {
+                // unless the semaphore is handed over or removed, the wait
+                // was interrupted
+                context.set_rax(Errno::EINTR.as_ret());
+                self.current().write().set_waiting_sem(Some(key));
+                self.block_current(context);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn sem_signal(&self, key: u32) -> Result<(), Errno> {
+        let ret = self.current().read().semaphores.read().signal(key);
+
+        match ret {
+            SemaphoreResult::Ok => Ok(()),
+            SemaphoreResult::NotExist => Err(Errno::ENOENT),
+            SemaphoreResult::WakeUp(pid) => {
+                self.wake_sem_waiter(pid, 0);
+                Ok(())
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Block the current process until the monotonic clock reaches `deadline`
+    pub fn sleep(&self, deadline: u64, context: &mut ProcessContext) {
+        let pid = processor::get_pid();
+        self.timer_queue.lock().push(Reverse((deadline, pid)));
+        self.block_current(context);
+    }
+
+    /// Drop the deadline of `pid`, if it is sleeping
+    fn cancel_sleep(&self, pid: ProcessId) {
+        self.timer_queue
+            .lock()
+            .retain(|Reverse((_, sleeper))| *sleeper != pid);
+    }
+
+    /// Wake up the processes whose deadline has passed
+    pub fn wake_sleeping(&self, now: u64) {
+        loop {
+            let pid = {
+                let mut timer_queue = self.timer_queue.lock();
+                match timer_queue.peek() {
+                    Some(Reverse((deadline, pid))) if *deadline <= now => {
+                        let pid = *pid;
+                        timer_queue.pop();
+                        pid
+                    }
+                    _ => return,
+                }
+            };
+
+            self.wake_up(pid);
+        }
+    }
+
     pub fn switch_next(&self, context: &mut ProcessContext) -> ProcessId {
         let mut pid = processor::get_pid();
         // FIXME: fetch the next process from ready queue
@@ -149,7 +326,10 @@ impl ProcessManager {
 
         while let Some(next) = self.ready_queue.lock().pop_front() {
             let map = self.processes.read();
-            let proc = map.get(&next).expect("Process not found");
+            // reaped while it was queued
+            let Some(proc) = map.get(&next) else {
+                continue;
+            };
 
             if !proc.read().is_ready() {
                 debug!("Process #{} is {:?}", next, proc.read().status());
@@ -210,6 +390,23 @@ impl ProcessManager {
         }
 
         let process = current_process.unwrap();
+
+        // write to a page shared with a forked process,
+        // only needs a read lock since the kernel may fault while holding one
+        if err_code.contains(
+            PageFaultErrorCode::PROTECTION_VIOLATION | PageFaultErrorCode::CAUSED_BY_WRITE,
+        ) && process.read().handle_cow_page_fault(addr)
+        {
+            return true;
+        }
+
+        // first access to a memory mapped region, also only needs a read lock
+        if !err_code.contains(PageFaultErrorCode::PROTECTION_VIOLATION)
+            && process.read().handle_vma_page_fault(addr, err_code)
+        {
+            return true;
+        }
+
         let mut process_inner = process.write();
 
         // 检查是否为越权访问错误
@@ -296,7 +493,22 @@ impl ProcessManager {
             }
         }
 
-        // TODO: print memory usage of kernel heap
+        let info = self.sys_info();
+        let (used, used_unit) = crate::humanized_size(info.frames_used * PAGE_SIZE);
+        let (total, total_unit) = crate::humanized_size(info.frames_total * PAGE_SIZE);
+        output += format!(
+            "Memory : {:>7.*} {} / {:>7.*} {}\n",
+            3, used, used_unit, 3, total, total_unit
+        )
+        .as_str();
+
+        let (used, used_unit) = crate::humanized_size(info.heap_used);
+        let (total, total_unit) = crate::humanized_size(info.heap_total);
+        output += format!(
+            "Heap   : {:>7.*} {} / {:>7.*} {}\n",
+            3, used, used_unit, 3, total, total_unit
+        )
+        .as_str();
 
         output += format!("Queue  : {:?}\n", self.ready_queue.lock()).as_str();
 
@@ -304,7 +516,37 @@ impl ProcessManager {
 
         print!("{}", output);
     }
-    
+
+    pub fn sys_info(&self) -> SysInfo {
+        let version = env!("CARGO_PKG_VERSION").as_bytes();
+        let version_len = version.len().min(VERSION_LEN);
+
+        let mut info = SysInfo {
+            version_len: version_len as u8,
+            processes: self.processes.read().len() as u16,
+            cpus: processor::cpu_count() as u32,
+            uptime: crate::interrupt::clock::read_counter(),
+            tick_ns: crate::interrupt::clock::NANOS_PER_TICK,
+            ..Default::default()
+        };
+        info.version[..version_len].copy_from_slice(&version[..version_len]);
+
+        {
+            let frame_alloc = get_frame_alloc_for_sure();
+            info.frames_total = frame_alloc.frames_total() as u64;
+            info.frames_used = frame_alloc.frames_used() as u64;
+        }
+
+        info.heap_total = HEAP_SIZE as u64;
+        info.heap_used = ALLOCATOR.lock().used() as u64;
+        info
+    }
+
+    /// Snapshots of all processes, ordered by pid
+    pub fn proc_list(&self) -> Vec<ProcessInfo> {
+        self.processes.read().values().map(|p| p.info()).collect()
+    }
+
     pub fn get_exit_code(&self, pid: ProcessId) -> Option<isize> {
         let proc_opt = self.get_proc(&pid);
         if let Some(proc) = proc_opt {
@@ -325,52 +567,210 @@ impl ProcessManager {
         name: String,
         parent: Option<Weak<Process>>,
         proc_data: Option<ProcessData>,
-    ) -> ProcessId {
+        argv: &[String],
+        envp: Option<&[String]>,
+    ) -> Result<ProcessId, Errno> {
         let kproc = self.get_proc(&KERNEL_PID).unwrap();
         let page_table = kproc.read().clone_page_table();
-        let proc = Process::new(name, parent, page_table, proc_data);
+        // inherit the environment and open file descriptors from the parent
+        let proc_data = proc_data.or_else(|| {
+            let parent = parent.as_ref()?.upgrade()?;
+            let parent = parent.read();
+            parent.proc_data().map(|data| data.inherit())
+        });
+        let proc = Process::new(name, parent, page_table, proc_data)?;
         let pid = proc.pid();
-        // info!("1"); Y
-        let mut inner = proc.write();
-        // FIXME: load elf to process pagetable
-        // inner.load_elf(elf);
-        // // FIXME: alloc new stack for process
-        // inner.init_stack_frame(
-        //     VirtAddr::new_truncate(elf.header.pt2.entry_point()),
-        //     VirtAddr::new_truncate(STACK_INIT_TOP),
-        // );
-        // // FIXME: mark process as ready
-        // // drop(inner);
+        let parent = proc.read().parent();
 
+        let mut inner = proc.write();
         inner.pause();
-        // info!("2"); Y
-        // trace!("New {:#?}", &proc);
-        // info!("3");
-        let stack_bot = inner.load_elf(elf, pid.0 as u64);
-        let stack_top = stack_bot + STACK_DEF_SIZE - 8;
-        // info!("4");
-        inner.init_stack_frame(
-            VirtAddr::new_truncate(elf.header.pt2.entry_point()), //获取 ELF 文件的入口地址
-            VirtAddr::new_truncate(stack_top),
-        );
-        // info!("5");
+        if let Some(envp) = envp {
+            inner.set_envs(envp);
+        }
+        let envp = inner.envs();
+        if let Err(errno) = inner.load_program(elf, pid.0 as u64, argv, &envp) {
+            // never added to the process table
+            pid.release();
+            return Err(errno);
+        }
         drop(inner);
 
-        // FIXME: something like kernel thread
+        match parent {
+            Some(parent) if parent.pid() != KERNEL_PID => {
+                let mut parent = parent.write();
+                let mut inner = proc.write();
+                inner.set_pgid(parent.pgid());
+                inner.set_sid(parent.sid());
+                parent.add_child(proc.clone());
+            }
+            // apps started by the kernel lead their own session, and own the console
+            parent => {
+                if let Some(parent) = parent {
+                    parent.write().add_child(proc.clone());
+                }
+                *self.foreground.lock() = Some(pid);
+                *self.console_session.lock() = Some(pid);
+            }
+        }
         self.add_proc(pid, proc);
-        // info!("6");
         self.push_ready(pid);
 
         self.print_process_list();
 
-        pid
+        Ok(pid)
+    }
+
+    pub fn fork(&self) -> Result<ProcessId, Errno> {
+        let kproc = self.get_proc(&KERNEL_PID).unwrap();
+        let page_table = kproc.read().clone_page_table();
+        let child = self.current().fork(page_table)?;
+        let pid = child.pid();
+
+        self.add_proc(pid, child);
+        self.push_ready(pid);
+
+        Ok(pid)
+    }
+
+    /// The process owning the address space of `proc`, itself unless it is a thread
+    fn owner(&self, proc: Arc<Process>) -> Arc<Process> {
+        let parent = proc.read().is_thread().then(|| proc.read().parent()).flatten();
+        parent.unwrap_or(proc)
+    }
+
+    /// Start a thread of the current process, threads of a thread belong to its owner
+    pub fn thread_create(
+        &self,
+        entry: VirtAddr,
+        stack_top: VirtAddr,
+        arg: usize,
+    ) -> Result<ProcessId, Errno> {
+        let thread = self.owner(self.current()).thread(entry, stack_top, arg)?;
+        let tid = thread.pid();
+
+        self.add_proc(tid, thread);
+        self.push_ready(tid);
+
+        Ok(tid)
+    }
+
+    /// Wait for the exit of the thread `pid`, returns its exit code if it is dead
+    ///
+    /// Only the other threads of the caller's process can be joined.
+    /// Otherwise the caller is blocked and the syscall is restarted when
+    /// `pid` exits.
+    pub fn join(&self, pid: ProcessId, context: &mut ProcessContext) -> Result<Option<isize>, Errno> {
+        let current = processor::get_pid();
+        let proc = self.get_proc(&pid).ok_or(Errno::ESRCH)?;
+        let owner = self.owner(self.current());
+
+        let is_own_thread = {
+            let inner = proc.read();
+            inner.is_thread() && inner.parent().is_some_and(|p| p.pid() == owner.pid())
+        };
+        if pid == current || !is_own_thread {
+            return Err(Errno::EINVAL);
+        }
+
+        let mut inner = proc.write();
+        if let Some(ret) = inner.exit_code() {
+            drop(inner);
+            self.reap(pid);
+            return Ok(Some(ret));
+        }
+
+        inner.add_exit_waiter(current);
+        drop(inner);
+
+        context.restart_syscall();
+        self.block_current(context);
+        Ok(None)
+    }
+
+    pub fn exec(
+        &self,
+        name: &str,
+        args: &[String],
+        envs: Option<&[String]>,
+        context: &mut ProcessContext,
+    ) -> Result<(), Errno> {
+        let app = self
+            .app_list()
+            .and_then(|apps| apps.iter().find(|app| app.name.eq(name)))
+            .ok_or(Errno::ENOENT)?;
+
+        let mut argv = Vec::with_capacity(args.len() + 1);
+        argv.push(String::from(app.name.as_str()));
+        argv.extend_from_slice(args);
+
+        let current = self.current();
+        let mut inner = current.write();
+        // the environment is kept unless a new one is given
+        let envp = match envs {
+            Some(envs) => envs.to_vec(),
+            None => inner.envs(),
+        };
+        inner.exec(&app.name, &app.elf, current.pid().0 as u64, &argv, &envp)?;
+        if envs.is_some() {
+            inner.set_envs(&envp);
+        }
+        inner.restore(context);
+
+        Ok(())
+    }
+
+    pub fn open(&self, path: &str) -> Result<u8, Errno> {
+        let res = Resource::open(path).ok_or(Errno::ENOENT)?;
+        self.current().read().open(res).ok_or(Errno::EMFILE)
+    }
+
+    pub fn close(&self, fd: u8) -> bool {
+        self.current().read().close(fd)
+    }
+
+    pub fn dup(&self, fd: u8) -> Result<u8, Errno> {
+        self.current().read().dup(fd)
+    }
+
+    pub fn dup2(&self, fd: u8, new_fd: u8) -> Result<u8, Errno> {
+        self.current().read().dup2(fd, new_fd)
     }
 
-    pub fn read(&self, fd: u8, buf: &mut [u8]) -> isize {
+    pub fn pipe(&self) -> Result<(u8, u8), Errno> {
+        let (read_end, write_end) = Pipe::new();
+        let current = self.current();
+        let inner = current.read();
+
+        let read_fd = inner.open(Resource::Pipe(read_end)).ok_or(Errno::EMFILE)?;
+        let Some(write_fd) = inner.open(Resource::Pipe(write_end)) else {
+            inner.close(read_fd);
+            return Err(Errno::EMFILE);
+        };
+
+        Ok((read_fd, write_fd))
+    }
+
+    pub fn wait_read(&self, fd: u8) -> bool {
+        let current = self.current();
+        let pid = current.pid();
+        let inner = current.read();
+        let wait = inner.resources.read().wait_read(fd, pid);
+        wait
+    }
+
+    pub fn wait_write(&self, fd: u8) -> bool {
+        let current = self.current();
+        let pid = current.pid();
+        let inner = current.read();
+        let wait = inner.resources.read().wait_write(fd, pid);
+        wait
+    }
+
+    pub fn read(&self, fd: u8, buf: &mut [u8]) -> Result<usize, Errno> {
         self.current().read().read(fd, buf)
     }
 
-    pub fn write(&self, fd: u8, buf: &[u8]) -> isize {
+    pub fn write(&self, fd: u8, buf: &[u8]) -> Result<usize, Errno> {
         self.current().write().write(fd, buf)
     }
 
@@ -381,9 +781,331 @@ impl ProcessManager {
     }
 
     pub fn kill(&self, pid: ProcessId, ret: isize) {
-        if let Some(process) = self.get_proc(&pid) {
-            info!("Process: {} is killed", pid);
-            process.kill(ret);
+        let Some(process) = self.get_proc(&pid) else {
+            return;
+        };
+        if process.read().status() == ProgramStatus::Dead {
+            return;
+        }
+
+        info!("Process: {} is killed", pid);
+        // leave the semaphore queue, the set is shared with other processes
+        if let Some(key) = process.write().take_waiting_sem() {
+            process.read().semaphores.read().cancel(key, pid);
+        }
+        process.kill(ret);
+
+        let pgid = process.read().pgid();
+        self.release_foreground(pgid);
+
+        let waiters = process.write().take_exit_waiters();
+        for waiter in waiters {
+            self.wake_up(waiter);
+        }
+
+        // threads do not outlive their process, and are reaped with its children
+        if !process.read().is_thread() {
+            let threads: Vec<ProcessId> = process
+                .read()
+                .children()
+                .iter()
+                .filter(|child| child.read().is_thread())
+                .map(|child| child.pid())
+                .collect();
+            for tid in threads {
+                self.kill(tid, ret);
+            }
+        }
+
+        self.reparent_children(&process);
+
+        let parent = process.read().parent();
+        match parent {
+            Some(parent) if parent.pid() != KERNEL_PID => {
+                let waiting = {
+                    let mut parent = parent.write();
+                    parent.raise(Signal::SIGCHLD);
+                    parent.take_waiting_child()
+                };
+                if waiting {
+                    self.wake_up(parent.pid());
+                }
+            }
+            // the kernel process never waits, its children are reaped right away
+            _ => self.reap(pid),
+        }
+    }
+
+    /// Hand the children of a dead process over to the kernel process
+    ///
+    /// The kernel process is the root of the process tree, and plays the
+    /// part of init: the dead children are reaped, the others on exit.
+    fn reparent_children(&self, process: &Arc<Process>) {
+        let children = process.write().take_children();
+        if children.is_empty() {
+            return;
+        }
+
+        let kproc = self.get_proc(&KERNEL_PID).unwrap();
+        for child in children {
+            if child.read().status() == ProgramStatus::Dead {
+                self.reap(child.pid());
+                continue;
+            }
+
+            debug!("Process #{} is adopted by the kernel", child.pid());
+            child.write().set_parent(Arc::downgrade(&kproc));
+            kproc.write().add_child(child);
+        }
+    }
+
+    /// Remove the dead process `pid` from the process table and free its pid
+    ///
+    /// A process still in use, as the current one or through its kernel
+    /// stack, is reaped later by `reap_deferred`.
+    fn reap(&self, pid: ProcessId) {
+        let Some(process) = self.get_proc(&pid) else {
+            return;
+        };
+        if process.read().status() != ProgramStatus::Dead {
+            return;
+        }
+
+        if pid == processor::get_pid() || process.read().on_kernel_stack() {
+            self.deferred_reap.lock().push(pid);
+            return;
+        }
+
+        self.processes.write().remove(&pid);
+        let parent = process.read().parent();
+        if let Some(parent) = parent {
+            parent.write().remove_child(pid);
+        }
+        self.forget(pid);
+        pid.release();
+
+        trace!("Reaped process #{}", pid);
+    }
+
+    /// Drop the references to `pid` kept outside of its process, before it is reused
+    fn forget(&self, pid: ProcessId) {
+        self.cancel_sleep(pid);
+
+        for process in self.processes.read().values() {
+            process.write().remove_exit_waiter(pid);
+        }
+
+        self.release_foreground(pid);
+
+        // the pid may lead another session once it is reused
+        let mut console_session = self.console_session.lock();
+        if *console_session == Some(pid) {
+            *console_session = None;
+        }
+    }
+
+    /// Take the console back from the group `pgid` once it has no live process
+    fn release_foreground(&self, pgid: ProcessId) {
+        let mut foreground = self.foreground.lock();
+        if *foreground == Some(pgid) && !self.group_alive(pgid) {
+            *foreground = None;
+        }
+    }
+
+    /// Reap the processes that were still in use when they exited
+    pub fn reap_deferred(&self) {
+        let pids = core::mem::take(&mut *self.deferred_reap.lock());
+        for pid in pids {
+            self.reap(pid);
+        }
+    }
+
+    /// Start or stop recording the syscalls of `pid` for the current process
+    ///
+    /// Only the caller itself and its children can be traced.
+    pub fn set_traced(&self, pid: ProcessId, traced: bool) -> Result<(), Errno> {
+        let current = self.current().pid();
+        let process = self.get_proc(&pid).ok_or(Errno::ESRCH)?;
+
+        let mut inner = process.write();
+        if inner.status() == ProgramStatus::Dead {
+            return Err(Errno::ESRCH);
+        }
+        if pid != current && inner.parent().map(|parent| parent.pid()) != Some(current) {
+            return Err(Errno::EPERM);
+        }
+        inner.set_tracer(traced.then_some(current));
+        Ok(())
+    }
+
+    /// Make `sig` pending for `pid`, a blocked process is woken up to handle it
+    pub fn signal(&self, pid: ProcessId, sig: Signal) -> bool {
+        let Some(process) = self.get_proc(&pid) else {
+            return false;
+        };
+
+        {
+            let mut inner = process.write();
+            if inner.status() == ProgramStatus::Dead {
+                return false;
+            }
+            inner.raise(sig);
+            if matches!(sig, Signal::SIGCONT | Signal::SIGKILL) {
+                inner.cont();
+            }
+        }
+
+        self.wake_up(pid);
+        true
+    }
+
+    /// Send `sig` to the live processes of group `pgid`, returns how many
+    pub fn signal_group(&self, pgid: ProcessId, sig: Signal) -> usize {
+        let members: Vec<ProcessId> = self
+            .processes
+            .read()
+            .values()
+            .filter(|p| p.pid() != KERNEL_PID && p.read().pgid() == pgid)
+            .map(|p| p.pid())
+            .collect();
+
+        members
+            .into_iter()
+            .filter(|&pid| self.signal(pid, sig))
+            .count()
+    }
+
+    /// Send `sig` to the foreground process group of the console
+    pub fn signal_foreground(&self, sig: Signal) {
+        let foreground = *self.foreground.lock();
+        if let Some(pgid) = foreground {
+            self.signal_group(pgid, sig);
+        }
+    }
+
+    /// Whether a live process is in the group `pgid`
+    fn group_alive(&self, pgid: ProcessId) -> bool {
+        self.processes
+            .read()
+            .values()
+            .any(|p| p.read().pgid() == pgid && p.read().status() != ProgramStatus::Dead)
+    }
+
+    /// Whether a live process of `sid` is in the group `pgid`
+    fn group_in_session(&self, pgid: ProcessId, sid: ProcessId) -> bool {
+        self.processes.read().values().any(|p| {
+            let inner = p.read();
+            inner.pgid() == pgid && inner.sid() == sid && inner.status() != ProgramStatus::Dead
+        })
+    }
+
+    /// The current process if `pid` is 0
+    fn target(&self, pid: ProcessId) -> Result<Arc<Process>, Errno> {
+        match pid.0 {
+            0 => Ok(self.current()),
+            _ => self.get_proc(&pid).ok_or(Errno::ESRCH),
+        }
+    }
+
+    /// Move `pid` (0 for the caller) to the group `pgid` (0 for its own pid)
+    ///
+    /// The target is the caller or one of its children, in the caller's
+    /// session and not a session leader. The group must be new or belong to
+    /// the same session.
+    pub fn set_pgid(&self, pid: ProcessId, pgid: ProcessId) -> Result<(), Errno> {
+        let current = self.current();
+        let target = self.target(pid)?;
+        let pgid = if pgid.0 == 0 { target.pid() } else { pgid };
+
+        let is_child = current.read().children().iter().any(|c| c.pid() == target.pid());
+        if target.pid() != current.pid() && !is_child {
+            return Err(Errno::ESRCH);
+        }
+
+        let sid = target.read().sid();
+        if sid != current.read().sid() || sid == target.pid() {
+            return Err(Errno::EPERM);
+        }
+        if pgid != target.pid() && !self.group_in_session(pgid, sid) {
+            return Err(Errno::EPERM);
+        }
+
+        target.write().set_pgid(pgid);
+        Ok(())
+    }
+
+    pub fn get_pgid(&self, pid: ProcessId) -> Result<ProcessId, Errno> {
+        Ok(self.target(pid)?.read().pgid())
+    }
+
+    /// Start a new session and group led by the caller, returns the session id
+    ///
+    /// Fails if the caller already leads a group.
+    pub fn set_sid(&self) -> Result<ProcessId, Errno> {
+        let current = self.current();
+        let pid = current.pid();
+
+        let mut inner = current.write();
+        if inner.pgid() == pid {
+            return Err(Errno::EPERM);
+        }
+        inner.set_pgid(pid);
+        inner.set_sid(pid);
+        Ok(pid)
+    }
+
+    pub fn get_sid(&self, pid: ProcessId) -> Result<ProcessId, Errno> {
+        Ok(self.target(pid)?.read().sid())
+    }
+
+    /// Give the console to the group `pgid` of the caller's session
+    pub fn set_foreground(&self, pgid: ProcessId) -> Result<(), Errno> {
+        let sid = self.current().read().sid();
+        if !self.group_in_session(pgid, sid) {
+            return Err(Errno::EPERM);
+        }
+
+        *self.foreground.lock() = Some(pgid);
+        Ok(())
+    }
+
+    pub fn foreground(&self) -> Option<ProcessId> {
+        *self.foreground.lock()
+    }
+
+    /// Whether the current process is in the session the kernel started on the console
+    pub fn is_privileged(&self) -> bool {
+        let sid = self.current().read().sid();
+        *self.console_session.lock() == Some(sid)
+    }
+
+    pub fn handle_signals(&self, context: &mut ProcessContext) {
+        // the next process may have pending signals as well
+        loop {
+            let current = self.current();
+            let Some(action) = current.write().deliver_signals(context) else {
+                return;
+            };
+
+            match action {
+                SigAction::Terminate(sig) => {
+                    info!("Process #{} is terminated by {:?}", current.pid(), sig);
+                    self.kill(current.pid(), signal::exit_code(sig));
+
+                    if self.switch_next(context) == current.pid() {
+                        return;
+                    }
+                }
+                SigAction::Stop(sig) => {
+                    info!("Process #{} is stopped by {:?}", current.pid(), sig);
+                    current.write().stop();
+                    self.block_current(context);
+
+                    if processor::get_pid() == current.pid() {
+                        return;
+                    }
+                }
+            }
         }
     }
     pub fn still_alive(&self, pid: ProcessId) -> bool {
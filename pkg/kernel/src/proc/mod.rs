@@ -1,11 +1,19 @@
+mod args;
 pub mod context;
 mod data;
+mod heap;
+mod kstack;
 pub mod manager;
-use crate::resource::Resource;
+use crate::resource::{Pipe, Resource};
 mod paging;
 mod pid;
 mod process;
 mod processor;
+mod shm;
+mod signal;
+mod vma;
+mod sync;
+mod uaccess;
 
 use crate::memory::PAGE_SIZE;
 use alloc::sync::Arc;
@@ -14,14 +22,20 @@ use manager::*;
 use process::*;
 
 use alloc::string::{String, ToString};
+use core::sync::atomic::{AtomicU32, Ordering};
 pub use context::ProcessContext;
 pub use data::ProcessData;
 pub use paging::PageTableContext;
 pub use pid::ProcessId;
+pub use signal::SigHandler;
+pub use uaccess::{UserPtr, UserSlice};
 
+use syscall_def::{
+    AppInfo, Errno, ProcessInfo, ProcessStatus, Signal, SysInfo, APP_NAME_LEN, VERSION_LEN,
+};
 use x86_64::structures::idt::PageFaultErrorCode;
 use x86_64::VirtAddr;
-use xmas_elf::ElfFile;
+use xmas_elf::{program, ElfFile};
 
 // 0xffff_ff00_0000_0000 is the kernel's address space
 pub const STACK_MAX: u64 = 0x0000_4000_0000_0000;
@@ -53,6 +67,17 @@ pub enum ProgramStatus {
     Dead,
 }
 
+impl From<ProgramStatus> for ProcessStatus {
+    fn from(status: ProgramStatus) -> Self {
+        match status {
+            ProgramStatus::Running => ProcessStatus::Running,
+            ProgramStatus::Ready => ProcessStatus::Ready,
+            ProgramStatus::Blocked => ProcessStatus::Blocked,
+            ProgramStatus::Dead => ProcessStatus::Dead,
+        }
+    }
+}
+
 /// init process manager
 pub fn init(boot_info: &'static boot::BootInfo) {
     let mut kproc_data = ProcessData::new();
@@ -69,6 +94,7 @@ pub fn init(boot_info: &'static boot::BootInfo) {
         let page_table = PageTableContext::new();
         // 创建内核进程
         Process::new(String::from("kernel"), None, page_table, Some(kproc_data))
+            .expect("Failed to create kernel process")
     };
     kproc.write().resume();
     // 初始化进程管理器并将内核进程设置为当前运行的进程
@@ -112,6 +138,16 @@ pub fn print_process_list() {
     })
 }
 
+/// Snapshots of all processes, see `Syscall::ProcList`
+pub fn proc_list() -> Vec<ProcessInfo> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().proc_list())
+}
+
+/// Uptime and memory statistics, see `Syscall::SysInfo`
+pub fn sys_info() -> SysInfo {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().sys_info())
+}
+
 pub fn env(key: &str) -> Option<String> {
     x86_64::instructions::interrupts::without_interrupts(|| {
         // 确保执行代码时中断禁用
@@ -120,6 +156,18 @@ pub fn env(key: &str) -> Option<String> {
     })
 }
 
+/// Set the environment variable `key` of the current process, `None` removes it
+pub fn set_env(key: &str, val: Option<&str>) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let current = get_process_manager().current();
+        let mut inner = current.write();
+        match val {
+            Some(val) => inner.set_env(key, val),
+            None => inner.remove_env(key),
+        }
+    })
+}
+
 pub fn process_exit(ret: isize) -> ! {
     x86_64::instructions::interrupts::without_interrupts(|| {
         get_process_manager().kill_current(ret);
@@ -168,37 +216,363 @@ pub fn list_app() {
     });
 }
 
-pub fn spawn(name: &str) -> Option<ProcessId> {
-    // info!("enter spawn");
+/// Descriptors of the apps loaded by the bootloader, see `Syscall::AppList`
+pub fn app_list() -> Vec<AppInfo> {
+    let apps = x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().app_list()
+    });
+
+    apps.map(|apps| apps.iter().map(app_info).collect())
+        .unwrap_or_default()
+}
+
+fn app_info(app: &boot::App) -> AppInfo {
+    let loads = app
+        .elf
+        .program_iter()
+        .filter(|header| header.get_type() == Ok(program::Type::Load));
+
+    let mut info = AppInfo {
+        name_len: app.name.len() as u8,
+        elf_size: app.elf.input.len() as u64,
+        entry: app.elf.header.pt2.entry_point(),
+        ..Default::default()
+    };
+    for header in loads {
+        info.segments += 1;
+        info.mem_size += header.mem_size();
+    }
+    info.name[..app.name.len().min(APP_NAME_LEN)].copy_from_slice(app.name.as_bytes());
+
+    info
+}
+
+/// Spawn the app `name` with extra arguments `args`, `argv[0]` is the app name
+///
+/// The child gets the environment `envs` (`KEY=VALUE`), or a copy of the
+/// environment of the current process if it is `None`.
+pub fn spawn(name: &str, args: &[String], envs: Option<&[String]>) -> Result<ProcessId, Errno> {
     let app = x86_64::instructions::interrupts::without_interrupts(|| {
-        // info!("1");
         let app_list = get_process_manager().app_list()?;
-        // info!("2");
-        // info!("{}", name);
         app_list.iter().find(|&app| app.name.eq(name))
-    })?;
-    // info!("3");
-    elf_spawn(name.to_string(), &app.elf)
+    })
+    .ok_or(Errno::ENOENT)?;
+
+    elf_spawn(name.to_string(), &app.elf, args, envs)
 }
 
-pub fn elf_spawn(name: String, elf: &ElfFile) -> Option<ProcessId> {
-    let pid = x86_64::instructions::interrupts::without_interrupts(|| {
+pub fn elf_spawn(
+    name: String,
+    elf: &ElfFile,
+    args: &[String],
+    envs: Option<&[String]>,
+) -> Result<ProcessId, Errno> {
+    let mut argv = Vec::with_capacity(args.len() + 1);
+    argv.push(name.clone());
+    argv.extend_from_slice(args);
+
+    x86_64::instructions::interrupts::without_interrupts(|| {
         let manager = get_process_manager();
         let parent = Arc::downgrade(&manager.current());
-        // info!("3"); Y
-        let pid = manager.spawn(elf, name.to_lowercase(), Some(parent), None);
+        manager.spawn(elf, name.to_lowercase(), Some(parent), None, &argv, envs)
+    })
+}
 
-        pid
-    });
+pub fn fork(context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        // save the parent context so that the child starts from the same point
+        let parent = manager.save_current(context);
+        if let Err(errno) = manager.fork() {
+            manager.current().write().set_return(errno.as_ret());
+        }
+        manager.push_ready(parent);
+        manager.switch_next(context);
+    })
+}
+
+/// Create a thread running `entry(arg)` on `stack_top` in the current process
+pub fn thread_create(
+    entry: VirtAddr,
+    stack_top: VirtAddr,
+    arg: usize,
+) -> Result<ProcessId, Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().thread_create(entry, stack_top, arg)
+    })
+}
+
+/// Wait until `pid` exits, returns its exit code if it is already dead
+///
+/// Otherwise the current process is blocked and the syscall restarted later.
+pub fn join(pid: ProcessId, context: &mut ProcessContext) -> Result<Option<isize>, Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().join(pid, context)
+    })
+}
+
+/// Replace the current process image with the app `name`
+///
+/// The environment is replaced by `envs` unless it is `None`.
+/// The context is left untouched if the app cannot be executed.
+pub fn exec(
+    name: &str,
+    args: &[String],
+    envs: Option<&[String]>,
+    context: &mut ProcessContext,
+) -> Result<(), Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().exec(name, args, envs, context)
+    })
+}
+
+pub fn open(path: &str) -> Result<u8, Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().open(path))
+}
+
+pub fn close(fd: u8) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().close(fd))
+}
 
-    Some(pid)
+pub fn dup(fd: u8) -> Result<u8, Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().dup(fd))
 }
 
-pub fn read(fd: u8, buf: &mut [u8]) -> isize {
+pub fn dup2(fd: u8, new_fd: u8) -> Result<u8, Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().dup2(fd, new_fd))
+}
+
+pub fn pipe() -> Result<(u8, u8), Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().pipe())
+}
+
+/// Block the current process if reading `fd` would block,
+/// the read syscall is issued again once the process is woken up.
+pub fn wait_read(fd: u8, context: &mut ProcessContext) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        if !manager.wait_read(fd) {
+            return false;
+        }
+        context.restart_syscall();
+        manager.block_current(context);
+        true
+    })
+}
+
+/// Block the current process if writing `fd` would block,
+/// the write syscall is issued again once the process is woken up.
+pub fn wait_write(fd: u8, context: &mut ProcessContext) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        if !manager.wait_write(fd) {
+            return false;
+        }
+        context.restart_syscall();
+        manager.block_current(context);
+        true
+    })
+}
+
+/// Move the program break of the current process, returns the new break
+pub fn brk(addr: Option<VirtAddr>) -> Option<VirtAddr> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().brk(addr)
+    })
+}
+
+/// Map `len` bytes into the current process, returns the start of the region
+pub fn mmap(hint: Option<VirtAddr>, len: u64, bits: usize) -> Option<VirtAddr> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().mmap(hint, len, bits)
+    })
+}
+
+pub fn munmap(addr: VirtAddr, len: u64) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().munmap(addr, len)
+    })
+}
+
+/// Create the shared memory segment `key` of at least `size` bytes
+pub fn shm_get(key: u32, size: usize) -> Result<(), Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| shm::get(key, size))
+}
+
+/// Remove the shared memory segment `key`, see `shm::remove`
+pub fn shm_remove(key: u32) -> Result<(), Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| shm::remove(key))
+}
+
+/// Map the shared memory segment `key` into the current process
+pub fn shm_attach(key: u32, hint: Option<VirtAddr>) -> Result<VirtAddr, Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().shm_attach(key, hint)
+    })
+}
+
+pub fn shm_detach(addr: VirtAddr) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().shm_detach(addr)
+    })
+}
+
+/// Create the semaphore `key` with `value`
+pub fn new_sem(key: u32, value: usize) -> Result<(), Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().new_sem(key, value)
+    })
+}
+
+pub fn remove_sem(key: u32) -> Result<(), Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().remove_sem(key))
+}
+
+/// Take the semaphore `key`, the current process is blocked until it is available
+pub fn sem_wait(key: u32, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().sem_wait(key, context)
+    })
+}
+
+pub fn sem_signal(key: u32) -> Result<(), Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().sem_signal(key))
+}
+
+/// Block the current process for `ns` nanoseconds
+pub fn sleep(ns: u64, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let deadline = crate::interrupt::clock::now_ns().saturating_add(ns);
+        context.set_rax(0);
+        get_process_manager().sleep(deadline, context);
+    })
+}
+
+pub fn wake_sleeping(now: u64) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().wake_sleeping(now)
+    })
+}
+
+pub fn wake_up(pid: ProcessId) {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().wake_up(pid))
+}
+
+/// Send `sig` to process `pid`, returns `false` if it does not exist
+pub fn kill(pid: ProcessId, sig: Signal) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().signal(pid, sig))
+}
+
+/// Send `sig` to the process group `pgid`, returns how many processes got it
+pub fn kill_group(pgid: ProcessId, sig: Signal) -> usize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().signal_group(pgid, sig)
+    })
+}
+
+/// Signals typed on the console and not sent yet, one bit per signal number
+static CONSOLE_SIGNALS: AtomicU32 = AtomicU32::new(0);
+
+/// Queue `sig` for the foreground group of the console
+///
+/// Called from the serial interrupt, which may have interrupted a holder of
+/// the manager locks, so the signal is only sent on the next timer tick.
+pub fn signal_foreground(sig: Signal) {
+    CONSOLE_SIGNALS.fetch_or(1 << sig as u32, Ordering::Relaxed);
+}
+
+/// Send the signals queued by `signal_foreground`
+pub fn send_console_signals() {
+    let pending = CONSOLE_SIGNALS.swap(0, Ordering::Relaxed);
+    if pending == 0 {
+        return;
+    }
+
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        for sig in (0..u32::BITS).filter(|bit| pending & (1 << bit) != 0) {
+            if let Ok(sig) = Signal::try_from(sig as usize) {
+                manager.signal_foreground(sig);
+            }
+        }
+    })
+}
+
+pub fn set_pgid(pid: ProcessId, pgid: ProcessId) -> Result<(), Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().set_pgid(pid, pgid)
+    })
+}
+
+pub fn get_pgid(pid: ProcessId) -> Result<ProcessId, Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().get_pgid(pid))
+}
+
+pub fn set_sid() -> Result<ProcessId, Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().set_sid())
+}
+
+pub fn get_sid(pid: ProcessId) -> Result<ProcessId, Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().get_sid(pid))
+}
+
+/// Make `pgid` the foreground group of the console
+pub fn set_foreground(pgid: ProcessId) -> Result<(), Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().set_foreground(pgid)
+    })
+}
+
+pub fn foreground() -> Option<ProcessId> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().foreground())
+}
+
+/// Whether the current process may power off or reboot the machine
+pub fn is_privileged() -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().is_privileged())
+}
+
+/// Set the handler of `sig` for the current process, returns the previous one
+pub fn sigaction(sig: Signal, handler: SigHandler, trampoline: u64) -> Option<SigHandler> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager()
+            .current()
+            .write()
+            .set_sig_handler(sig, handler, trampoline)
+    })
+}
+
+/// Return from a signal handler to the interrupted context
+pub fn sigreturn(context: &mut ProcessContext) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().write().sigreturn(context)
+    })
+}
+
+/// Deliver pending signals to the process about to return to user mode
+pub fn handle_signals(context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().handle_signals(context)
+    })
+}
+
+/// Send `sig` to the current process for a fault it caused
+///
+/// The faulting instruction is retried if the handler returns, so a signal
+/// that cannot be handled now terminates the process instead.
+pub fn fault_signal(sig: Signal, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        manager.current().write().force_signal(sig);
+        manager.handle_signals(context);
+    })
+}
+
+pub fn read(fd: u8, buf: &mut [u8]) -> Result<usize, Errno> {
     x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().read(fd, buf))
 }
 
-pub fn write(fd: u8, buf: &[u8]) -> isize {
+pub fn write(fd: u8, buf: &[u8]) -> Result<usize, Errno> {
     x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().write(fd, buf))
 }
 
@@ -219,17 +593,41 @@ pub fn still_alive(pid: ProcessId) -> bool {
     })
 }
 
-pub fn wait_pid(pid: ProcessId, context: &mut ProcessContext) {
+/// Collect the exited child `pid` of the current process, or any child if `None`
+///
+/// Returns its pid and exit code, or `None` if the children are running.
+/// Unless `nohang` is set, the current process is blocked in that case and
+/// the syscall restarted later.
+pub fn wait_pid(
+    pid: Option<ProcessId>,
+    nohang: bool,
+    context: &mut ProcessContext,
+) -> Result<Option<(ProcessId, isize)>, Errno> {
     x86_64::instructions::interrupts::without_interrupts(|| {
-        let manager = get_process_manager();
-        if let Some(ret) = manager.wait_pid(pid) {
-            context.set_rax(ret as usize)
-        } else {
-            manager.save_current(context);
-            manager.switch_next(context);
-        }
+        get_process_manager().wait_pid(pid, nohang, context)
+    })
+}
+
+/// Reap the dead processes that were still in use when they exited
+pub fn reap_deferred() {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().reap_deferred()
+    })
+}
+/// Process the syscalls of the current process are recorded for
+pub fn tracer() -> Option<ProcessId> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().tracer()
     })
 }
+
+/// Start or stop recording the syscalls of `pid` for the current process
+pub fn set_traced(pid: ProcessId, traced: bool) -> Result<(), Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().set_traced(pid, traced)
+    })
+}
+
 pub fn get_current_pid() -> ProcessId {
     x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().current().pid())
 }
\ No newline at end of file
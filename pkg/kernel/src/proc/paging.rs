@@ -4,10 +4,13 @@ use core::ptr::copy_nonoverlapping;
 use alloc::sync::Arc;
 use x86_64::{
     registers::control::{Cr3, Cr3Flags},
-    structures::paging::*,
+    structures::paging::{mapper::*, page::PageRange, *},
     VirtAddr,
 };
 
+/// Marks a user page that is shared read-only after fork and must be copied on write.
+pub const COW_FLAG: PageTableFlags = PageTableFlags::BIT_9;
+
 pub struct Cr3RegValue {
     pub addr: PhysFrame,
     pub flags: Cr3Flags,
@@ -19,10 +22,18 @@ impl Cr3RegValue {
     }
 }
 
+/// An address space, cloning it gives another reference to the same page table
+#[derive(Clone)]
 pub struct PageTableContext {
     pub reg: Arc<Cr3RegValue>,
 }
 
+impl Default for PageTableContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PageTableContext {
     pub fn new() -> Self {
         let (frame, flags) = Cr3::read();
@@ -54,6 +65,11 @@ impl PageTableContext {
         }
     }
 
+    /// Whether the page table is used by more than one thread
+    pub fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.reg) > 1
+    }
+
     /// Load the page table to Cr3 register.
     pub fn load(&self) {
         unsafe { Cr3::write(self.reg.addr, self.reg.flags) }
@@ -70,6 +86,226 @@ impl PageTableContext {
             )
         }
     }
+
+    /// Share the mapped pages of `pages` with `child` as copy-on-write.
+    ///
+    /// Writable pages lose their write permission in both page tables and are
+    /// marked with `COW_FLAG`, the first write fault will copy the frame.
+    pub fn share_cow(&self, child: &PageTableContext, pages: impl Iterator<Item = Page>) {
+        self.share_with(child, pages, true);
+    }
+
+    /// Map the mapped pages of `pages` to the same frames in `child`,
+    /// writes are seen by both processes.
+    pub fn share_pages(&self, child: &PageTableContext, pages: impl Iterator<Item = Page>) {
+        self.share_with(child, pages, false);
+    }
+
+    fn share_with(&self, child: &PageTableContext, pages: impl Iterator<Item = Page>, cow: bool) {
+        let mut mapper = self.mapper();
+        let mut child_mapper = child.mapper();
+        let frame_alloc = &mut *get_frame_alloc_for_sure();
+        let table_flags = PageTableFlags::PRESENT
+            | PageTableFlags::WRITABLE
+            | PageTableFlags::USER_ACCESSIBLE;
+
+        for page in pages {
+            let (frame, mut flags) = match mapper.translate(page.start_address()) {
+                TranslateResult::Mapped {
+                    frame: MappedFrame::Size4KiB(frame),
+                    flags,
+                    ..
+                } => (frame, flags),
+                // stack pages below the fault boundary may not be mapped yet
+                _ => continue,
+            };
+
+            if cow && flags.contains(PageTableFlags::WRITABLE) {
+                flags.remove(PageTableFlags::WRITABLE);
+                flags.insert(COW_FLAG);
+                unsafe {
+                    mapper
+                        .update_flags(page, flags)
+                        .expect("Failed to write protect parent page")
+                        .flush();
+                }
+            }
+
+            unsafe {
+                child_mapper
+                    .map_to_with_table_flags(page, frame, flags, table_flags, frame_alloc)
+                    .expect("Failed to map page for child")
+                    .ignore();
+            }
+
+            frame_alloc.share_frame(frame);
+        }
+    }
+
+    /// Copy `data` to `addr` of this address space through the physical memory mapping.
+    ///
+    /// Returns `false` if any page in the range is not mapped.
+    pub fn copy_to(&self, addr: VirtAddr, data: &[u8]) -> bool {
+        let mapper = self.mapper();
+        let mut offset = 0;
+
+        while offset < data.len() {
+            let cur = addr + offset as u64;
+            let Some(phys) = mapper.translate_addr(cur) else {
+                return false;
+            };
+
+            let in_page = (PAGE_SIZE - (cur.as_u64() & (PAGE_SIZE - 1))) as usize;
+            let len = in_page.min(data.len() - offset);
+
+            unsafe {
+                copy_nonoverlapping(
+                    data.as_ptr().add(offset),
+                    physical_to_virtual(phys.as_u64()) as *mut u8,
+                    len,
+                );
+            }
+
+            offset += len;
+        }
+
+        true
+    }
+
+    /// Map `pages` to new zeroed frames with `flags`.
+    ///
+    /// Returns `false` if the frames run out, the pages mapped so far are released.
+    pub fn map_pages(&self, pages: PageRange, flags: PageTableFlags) -> bool {
+        let mut mapper = self.mapper();
+        let mut frame_alloc = get_frame_alloc_for_sure();
+
+        for page in pages {
+            let mapped = frame_alloc.allocate_frame().is_some_and(|frame| unsafe {
+                core::ptr::write_bytes(
+                    physical_to_virtual(frame.start_address().as_u64()) as *mut u8,
+                    0,
+                    Size4KiB::SIZE as usize,
+                );
+
+                match mapper.map_to(page, frame, flags, &mut *frame_alloc) {
+                    Ok(flush) => {
+                        flush.flush();
+                        true
+                    }
+                    Err(_) => {
+                        frame_alloc.deallocate_frame(frame);
+                        false
+                    }
+                }
+            });
+
+            if !mapped {
+                drop(frame_alloc);
+                self.unmap_pages(Page::range(pages.start, page));
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Map `pages` to the existing `frames` with `flags`,
+    /// each frame gets one more reference.
+    ///
+    /// Returns `false` if a page cannot be mapped, the pages mapped so far are released.
+    pub fn map_frames(&self, pages: PageRange, frames: &[PhysFrame], flags: PageTableFlags) -> bool {
+        let mut mapper = self.mapper();
+        let mut frame_alloc = get_frame_alloc_for_sure();
+
+        for (page, frame) in pages.zip(frames) {
+            match unsafe { mapper.map_to(page, *frame, flags, &mut *frame_alloc) } {
+                Ok(flush) => {
+                    flush.flush();
+                    frame_alloc.share_frame(*frame);
+                }
+                Err(_) => {
+                    drop(frame_alloc);
+                    self.unmap_pages(Page::range(pages.start, page));
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Unmap the mapped pages of `pages` and release their frames.
+    pub fn unmap_pages(&self, pages: impl Iterator<Item = Page>) {
+        let mut mapper = self.mapper();
+        let frame_alloc = &mut *get_frame_alloc_for_sure();
+
+        for page in pages {
+            if let Ok((frame, flush)) = mapper.unmap(page) {
+                flush.flush();
+                unsafe { frame_alloc.release_frame(frame) };
+            }
+        }
+    }
+
+    /// Resolve a write fault on a copy-on-write page.
+    ///
+    /// Returns `false` if the page is not a copy-on-write page.
+    pub fn handle_cow_fault(&self, addr: VirtAddr) -> bool {
+        let mut mapper = self.mapper();
+        let page = Page::<Size4KiB>::containing_address(addr);
+
+        let (frame, mut flags) = match mapper.translate(page.start_address()) {
+            TranslateResult::Mapped {
+                frame: MappedFrame::Size4KiB(frame),
+                flags,
+                ..
+            } => (frame, flags),
+            _ => return false,
+        };
+
+        if !flags.contains(COW_FLAG) {
+            return false;
+        }
+
+        flags.remove(COW_FLAG);
+        flags.insert(PageTableFlags::WRITABLE);
+
+        let frame_alloc = &mut *get_frame_alloc_for_sure();
+
+        if frame_alloc.frame_refs(frame) == 1 {
+            // the last owner of the frame can just take it back
+            trace!("COW: reuse {:?} for {:?}", frame, page);
+            return unsafe { mapper.update_flags(page, flags) }
+                .map(|flush| flush.flush())
+                .is_ok();
+        }
+
+        let Some(new_frame) = frame_alloc.allocate_frame() else {
+            warn!("COW: no frame available for {:?}", page);
+            return false;
+        };
+
+        trace!("COW: copy {:?} -> {:?} for {:?}", frame, new_frame, page);
+
+        unsafe {
+            copy_nonoverlapping::<u8>(
+                physical_to_virtual(frame.start_address().as_u64()) as *const u8,
+                physical_to_virtual(new_frame.start_address().as_u64()) as *mut u8,
+                Size4KiB::SIZE as usize,
+            );
+        }
+
+        frame_alloc.unshare_frame(frame);
+
+        let Ok((_, flush)) = mapper.unmap(page) else {
+            return false;
+        };
+        flush.ignore();
+
+        unsafe { mapper.map_to(page, new_frame, flags, &mut *frame_alloc) }
+            .map(|flush| flush.flush())
+            .is_ok()
+    }
 }
 
 impl core::fmt::Debug for PageTableContext {
@@ -1,21 +1,57 @@
-use core::sync::atomic::{AtomicU16, Ordering};
+use spin::Mutex;
+use syscall_def::Errno;
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ProcessId(pub u16);
 
-static PID_COUNTER: AtomicU16 = AtomicU16::new(1); // Start from 1 to avoid using 0 as a PID
+/// Number of pids, the initial stacks of all processes must fit below `STACK_MAX`
+const PID_MAX: usize = 0x1000;
 
-impl ProcessId {
-    pub fn new() -> Self {
-         // Increment the global PID counter and use the new value as the PID
-         let pid = PID_COUNTER.fetch_add(1, Ordering::SeqCst);
-         ProcessId(pid)
+/// Pids in use, one bit each
+///
+/// Pids are handed out in increasing order and wrap around, so a freed pid
+/// is only reused after all the others were tried.
+struct PidAllocator {
+    used: [u64; PID_MAX / 64],
+    next: usize,
+}
+
+// Start from 1 to avoid using 0 as a PID, which is skipped when wrapping around
+static PIDS: Mutex<PidAllocator> = Mutex::new(PidAllocator {
+    used: [0; PID_MAX / 64],
+    next: 1,
+});
+
+impl PidAllocator {
+    fn alloc(&mut self) -> Option<u16> {
+        for _ in 0..PID_MAX {
+            let pid = self.next;
+            self.next = if pid + 1 == PID_MAX { 1 } else { pid + 1 };
+
+            let (word, bit) = (pid / 64, pid % 64);
+            if self.used[word] & (1 << bit) == 0 {
+                self.used[word] |= 1 << bit;
+                return Some(pid as u16);
+            }
+        }
+        None
+    }
+
+    fn free(&mut self, pid: u16) {
+        let pid = pid as usize;
+        self.used[pid / 64] &= !(1 << (pid % 64));
     }
 }
 
-impl Default for ProcessId {
-    fn default() -> Self {
-        Self::new()
+impl ProcessId {
+    /// Allocate an unused pid, fails with EAGAIN once all of them are in use
+    pub fn new() -> Result<Self, Errno> {
+        PIDS.lock().alloc().map(ProcessId).ok_or(Errno::EAGAIN)
+    }
+
+    /// Make the pid available again, once its process is reaped
+    pub fn release(self) {
+        PIDS.lock().free(self.0);
     }
 }
 
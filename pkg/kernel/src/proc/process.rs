@@ -1,10 +1,12 @@
+use super::args::ProgramStack;
+use super::kstack::KernelStack;
+use super::signal::{SigAction, SignalState};
+use super::vma::{Vma, MMAP_END, MMAP_START};
+use syscall_def::{MAP_SHARED, PROC_NAME_LEN, PROC_THREAD, PROC_TRACED, PROT_READ, PROT_WRITE};
 use super::*;
 use crate::memory::*;
-use alloc::sync::{Arc, Weak};
-use alloc::vec::Vec;
+use alloc::sync::Weak;
 use spin::*;
-use x86_64::structures::paging::mapper::MapToError;
-use x86_64::structures::paging::page::PageRange;
 use x86_64::structures::paging::*;
 
 #[derive(Clone)]
@@ -23,6 +25,25 @@ pub struct ProcessInner {
     context: ProcessContext,
     page_table: Option<PageTableContext>,
     proc_data: Option<ProcessData>,
+    signals: SignalState,
+    /// Shares the address space of its parent
+    is_thread: bool,
+    /// Process reading the recorded syscalls, inherited by forks and threads
+    tracer: Option<ProcessId>,
+    /// Processes blocked until this one exits
+    exit_waiters: Vec<ProcessId>,
+    /// Blocked in `wait_pid` until a child exits
+    waiting_child: bool,
+    /// Key of the semaphore it is queued on
+    waiting_sem: Option<u32>,
+    /// Process group, the console signals its foreground group
+    pgid: ProcessId,
+    /// Session, a set of process groups
+    sid: ProcessId,
+    /// Blocked by a stop signal until it is continued
+    stopped: bool,
+    /// Kept until the process is dropped, a dying process may still run on it
+    kernel_stack: KernelStack,
 }
 
 impl Process {
@@ -46,11 +67,12 @@ impl Process {
         parent: Option<Weak<Process>>,
         page_table: PageTableContext,
         proc_data: Option<ProcessData>,
-    ) -> Arc<Self> {
+    ) -> Result<Arc<Self>, Errno> {
         let name = name.to_ascii_lowercase();
 
-        // create context
-        let pid = ProcessId::new();
+        // the stack first, a pid is not leaked if it cannot be allocated
+        let kernel_stack = KernelStack::new()?;
+        let pid = ProcessId::new()?;
 
         let inner = ProcessInner {
             name,
@@ -62,15 +84,25 @@ impl Process {
             children: Vec::new(),
             page_table: Some(page_table),
             proc_data: Some(proc_data.unwrap_or_default()),
+            signals: SignalState::default(),
+            is_thread: false,
+            tracer: None,
+            exit_waiters: Vec::new(),
+            waiting_child: false,
+            waiting_sem: None,
+            pgid: pid,
+            sid: pid,
+            stopped: false,
+            kernel_stack,
         };
 
         trace!("New process {}#{} created.", &inner.name, pid);
 
         // create process struct
-        Arc::new(Self {
+        Ok(Arc::new(Self {
             pid,
             inner: Arc::new(RwLock::new(inner)),
-        })
+        }))
     }
 
     pub fn kill(&self, ret: isize) {
@@ -83,7 +115,102 @@ impl Process {
             ret
         );
 
-        inner.kill(ret);
+        let proc_data = inner.kill(ret);
+        drop(inner);
+
+        // resources like pipes may wake up other processes when dropped,
+        // which must not happen while this process is locked
+        drop(proc_data);
+    }
+
+    /// Duplicate the process into a new child process.
+    ///
+    /// The child gets a copy-on-write view of the parent's memory in
+    /// `page_table`, returns 0 from the syscall, and the parent gets the child pid.
+    pub fn fork(self: &Arc<Self>, page_table: PageTableContext) -> Result<Arc<Self>, Errno> {
+        let kernel_stack = KernelStack::new()?;
+        let child_pid = ProcessId::new()?;
+
+        let mut inner = self.inner.write();
+        let child_inner = inner.fork(Arc::downgrade(self), page_table, kernel_stack);
+
+        trace!(
+            "Fork process {}#{} -> #{}",
+            inner.name(),
+            self.pid,
+            child_pid
+        );
+
+        // parent returns the child pid from fork
+        inner.context.set_rax(child_pid.0 as usize);
+
+        let child = Arc::new(Self {
+            pid: child_pid,
+            inner: Arc::new(RwLock::new(child_inner)),
+        });
+
+        inner.children.push(child.clone());
+
+        Ok(child)
+    }
+
+    /// Create a thread of the process running `entry(arg)` on `stack_top`
+    pub fn thread(
+        self: &Arc<Self>,
+        entry: VirtAddr,
+        stack_top: VirtAddr,
+        arg: usize,
+    ) -> Result<Arc<Self>, Errno> {
+        let kernel_stack = KernelStack::new()?;
+        let tid = ProcessId::new()?;
+
+        let mut inner = self.inner.write();
+        let thread_inner = inner.thread(Arc::downgrade(self), entry, stack_top, arg, kernel_stack);
+
+        trace!("New thread {}#{} of #{}", inner.name(), tid, self.pid);
+
+        let thread = Arc::new(Self {
+            pid: tid,
+            inner: Arc::new(RwLock::new(thread_inner)),
+        });
+
+        inner.children.push(thread.clone());
+
+        Ok(thread)
+    }
+
+    /// Snapshot of the process for `Syscall::ProcList`
+    pub fn info(&self) -> ProcessInfo {
+        let inner = self.inner.read();
+
+        let mut flags = 0;
+        if inner.is_thread {
+            flags |= PROC_THREAD;
+        }
+        if inner.tracer.is_some() {
+            flags |= PROC_TRACED;
+        }
+
+        // truncated on a character boundary
+        let mut name_len = inner.name.len().min(PROC_NAME_LEN);
+        while !inner.name.is_char_boundary(name_len) {
+            name_len -= 1;
+        }
+
+        let mut info = ProcessInfo {
+            pid: self.pid.0,
+            ppid: inner.parent().map(|p| p.pid.0).unwrap_or(0),
+            status: ProcessStatus::from(inner.status) as u8,
+            flags,
+            name_len: name_len as u8,
+            ticks: inner.ticks_passed as u64,
+            memory: inner.proc_data.as_ref().map_or(0, |data| data.memory_usage()),
+            exit_code: inner.exit_code.unwrap_or(0),
+            ..Default::default()
+        };
+        info.name[..name_len].copy_from_slice(&inner.name.as_bytes()[..name_len]);
+
+        info
     }
 
     pub fn alloc_init_stack(&mut self) -> VirtAddr {
@@ -153,6 +280,271 @@ impl ProcessInner {
         self.status = ProgramStatus::Running;
     }
 
+    pub fn block(&mut self) {
+        self.status = ProgramStatus::Blocked;
+    }
+
+    pub fn raise(&mut self, sig: Signal) {
+        self.signals.raise(sig);
+    }
+
+    pub fn set_sig_handler(
+        &mut self,
+        sig: Signal,
+        handler: SigHandler,
+        trampoline: u64,
+    ) -> Option<SigHandler> {
+        self.signals.set_handler(sig, handler, trampoline)
+    }
+
+    /// Deliver pending signals to the running process,
+    /// returns the signal that terminates it, if any.
+    pub fn deliver_signals(&mut self, context: &mut ProcessContext) -> Option<SigAction> {
+        self.signals.deliver(context)
+    }
+
+    /// Raise `sig` for a fault, see `SignalState::force`
+    pub fn force_signal(&mut self, sig: Signal) {
+        self.signals.force(sig)
+    }
+
+    pub fn sigreturn(&mut self, context: &mut ProcessContext) -> bool {
+        self.signals.sigreturn(context)
+    }
+
+    /// Move the program break of the process, see `Heap::brk`
+    pub fn brk(&self, addr: Option<VirtAddr>) -> Option<VirtAddr> {
+        self.heap.brk(addr, self.page_table.as_ref()?)
+    }
+
+    /// Map `len` bytes at `hint` or anywhere in the mmap area, see `Syscall::Mmap`
+    ///
+    /// Private regions are populated on page faults, shared ones right away.
+    pub fn mmap(&self, hint: Option<VirtAddr>, len: u64, bits: usize) -> Option<VirtAddr> {
+        let count = len.div_ceil(PAGE_SIZE);
+        if count == 0 || count > (MMAP_END - MMAP_START) / PAGE_SIZE {
+            return None;
+        }
+
+        let page_table = self.page_table.as_ref()?;
+        let proc_data = self.proc_data.as_ref()?;
+        let vma = proc_data.reserve_vma(hint, count, bits, None)?;
+
+        if vma.shared
+            && vma.flags.contains(PageTableFlags::PRESENT)
+            && !page_table.map_pages(vma.pages, vma.flags)
+        {
+            proc_data.remove_vmas(vma.pages);
+            return None;
+        }
+
+        trace!("Mmap: {:?}", vma);
+        Some(vma.pages.start.start_address())
+    }
+
+    /// Unmap the regions in `[addr, addr + len)`, `addr` must be page aligned
+    pub fn munmap(&self, addr: VirtAddr, len: u64) -> bool {
+        if !addr.is_aligned(PAGE_SIZE) || len == 0 {
+            return false;
+        }
+
+        let Some(end) = addr
+            .as_u64()
+            .checked_add(len.next_multiple_of(PAGE_SIZE))
+            .and_then(|end| VirtAddr::try_new(end).ok())
+        else {
+            return false;
+        };
+
+        let (Some(page_table), Some(proc_data)) = (&self.page_table, &self.proc_data) else {
+            return false;
+        };
+
+        let pages = Page::range(Page::containing_address(addr), Page::containing_address(end));
+
+        // shared memory segments are only unmapped by `shm_detach`
+        let is_shm = |vma: &Vma| vma.shm.is_some() && vma.overlaps(pages);
+        if proc_data.vmas.read().iter().any(is_shm) {
+            return false;
+        }
+
+        for range in proc_data.remove_vmas(pages) {
+            page_table.unmap_pages(range);
+        }
+
+        true
+    }
+
+    /// Map the shared memory segment `key` at `hint` or anywhere in the mmap area
+    pub fn shm_attach(&self, key: u32, hint: Option<VirtAddr>) -> Result<VirtAddr, Errno> {
+        let frames = shm::attach(key).ok_or(Errno::ENOENT)?;
+
+        let (Some(page_table), Some(proc_data)) = (&self.page_table, &self.proc_data) else {
+            shm::detach(key);
+            return Err(Errno::EINVAL);
+        };
+
+        let bits = PROT_READ | PROT_WRITE | MAP_SHARED;
+        let Some(vma) = proc_data.reserve_vma(hint, frames.len() as u64, bits, Some(key)) else {
+            shm::detach(key);
+            return Err(Errno::ENOMEM);
+        };
+
+        if !page_table.map_frames(vma.pages, &frames, vma.flags) {
+            proc_data.remove_vmas(vma.pages);
+            shm::detach(key);
+            return Err(Errno::ENOMEM);
+        }
+
+        trace!("Shm attach: {:?}", vma);
+        Ok(vma.pages.start.start_address())
+    }
+
+    /// Unmap the shared memory segment attached at `addr`
+    pub fn shm_detach(&self, addr: VirtAddr) -> bool {
+        let (Some(page_table), Some(proc_data)) = (&self.page_table, &self.proc_data) else {
+            return false;
+        };
+
+        match proc_data.take_shm_vma(addr) {
+            Some(vma) => {
+                vma.release(page_table);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Populate a page of a memory mapped region on first access
+    pub fn handle_vma_page_fault(&self, addr: VirtAddr, err_code: PageFaultErrorCode) -> bool {
+        let (Some(page_table), Some(proc_data)) = (&self.page_table, &self.proc_data) else {
+            return false;
+        };
+
+        let Some(vma) = proc_data.find_vma(addr) else {
+            return false;
+        };
+
+        let flags = vma.flags;
+        if !flags.contains(PageTableFlags::PRESENT)
+            || (err_code.contains(PageFaultErrorCode::CAUSED_BY_WRITE)
+                && !flags.contains(PageTableFlags::WRITABLE))
+            || (err_code.contains(PageFaultErrorCode::INSTRUCTION_FETCH)
+                && flags.contains(PageTableFlags::NO_EXECUTE))
+        {
+            return false;
+        }
+
+        let page = Page::containing_address(addr);
+        page_table.map_pages(Page::range(page, page + 1), flags)
+    }
+
+    pub fn is_thread(&self) -> bool {
+        self.is_thread
+    }
+
+    pub fn tracer(&self) -> Option<ProcessId> {
+        self.tracer
+    }
+
+    pub fn set_tracer(&mut self, tracer: Option<ProcessId>) {
+        self.tracer = tracer;
+    }
+
+    /// Block `pid` until this process exits
+    pub fn add_exit_waiter(&mut self, pid: ProcessId) {
+        self.exit_waiters.push(pid);
+    }
+
+    pub fn take_exit_waiters(&mut self) -> Vec<ProcessId> {
+        core::mem::take(&mut self.exit_waiters)
+    }
+
+    pub fn remove_exit_waiter(&mut self, pid: ProcessId) {
+        self.exit_waiters.retain(|&waiter| waiter != pid);
+    }
+
+    pub fn set_waiting_child(&mut self, waiting: bool) {
+        self.waiting_child = waiting;
+    }
+
+    /// Whether the process was blocked in `wait_pid`, and clear it
+    pub fn take_waiting_child(&mut self) -> bool {
+        core::mem::take(&mut self.waiting_child)
+    }
+
+    pub fn set_waiting_sem(&mut self, key: Option<u32>) {
+        self.waiting_sem = key;
+    }
+
+    /// The semaphore the process was queued on, and clear it
+    pub fn take_waiting_sem(&mut self) -> Option<u32> {
+        self.waiting_sem.take()
+    }
+
+    /// Set the return value of the syscall saved in the context
+    pub fn set_return(&mut self, value: usize) {
+        self.context.set_rax(value);
+    }
+
+    pub fn children(&self) -> &[Arc<Process>] {
+        &self.children
+    }
+
+    pub fn add_child(&mut self, child: Arc<Process>) {
+        self.children.push(child);
+    }
+
+    pub fn remove_child(&mut self, pid: ProcessId) {
+        self.children.retain(|child| child.pid() != pid);
+    }
+
+    pub fn take_children(&mut self) -> Vec<Arc<Process>> {
+        core::mem::take(&mut self.children)
+    }
+
+    pub fn set_parent(&mut self, parent: Weak<Process>) {
+        self.parent = Some(parent);
+    }
+
+    pub fn pgid(&self) -> ProcessId {
+        self.pgid
+    }
+
+    pub fn set_pgid(&mut self, pgid: ProcessId) {
+        self.pgid = pgid;
+    }
+
+    pub fn sid(&self) -> ProcessId {
+        self.sid
+    }
+
+    pub fn set_sid(&mut self, sid: ProcessId) {
+        self.sid = sid;
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Mark the process stopped, it is blocked until continued
+    pub fn stop(&mut self) {
+        self.stopped = true;
+    }
+
+    pub fn cont(&mut self) {
+        self.stopped = false;
+    }
+
+    /// Whether the kernel is running on the kernel stack of the process
+    pub fn on_kernel_stack(&self) -> bool {
+        self.kernel_stack.is_active()
+    }
+
+    pub fn proc_data(&self) -> Option<&ProcessData> {
+        self.proc_data.as_ref()
+    }
+
     pub fn exit_code(&self) -> Option<isize> {
         self.exit_code
     }
@@ -180,6 +572,7 @@ impl ProcessInner {
     pub(super) fn restore(&mut self, context: &mut ProcessContext) {
         // FIXME: restore the process's context
         self.context.restore(context);
+        crate::interrupt::syscall::set_kernel_stack(self.kernel_stack.top());
         if let Some(page_table) = self.page_table.as_ref() {
             page_table.load();
             self.resume();
@@ -191,7 +584,8 @@ impl ProcessInner {
         self.parent.as_ref().and_then(|p| p.upgrade())
     }
 
-    pub fn kill(&mut self, ret: isize) {
+    /// Mark the process as dead, returns the process data to be dropped by the caller
+    pub fn kill(&mut self, ret: isize) -> Option<ProcessData> {
         // FIXME: set exit code
         self.exit_code = Some(ret);
         // FIXME: set status to dead
@@ -201,7 +595,15 @@ impl ProcessInner {
         // self.page_table = None;
         // self.proc_data = None;
         // 改为lab4的删除进程数据
-        self.proc_data.take();
+        if let (Some(page_table), Some(proc_data)) = (&self.page_table, &self.proc_data) {
+            // the memory is still used by other threads of the process
+            if !page_table.is_shared() {
+                proc_data.heap.clean_up(page_table);
+                for vma in proc_data.vmas.write().drain(..) {
+                    vma.release(page_table);
+                }
+            }
+        }
         self.page_table.take();
         info!("kill completed,status {:#?}",self.status);
         // for child in self.children.iter(){
@@ -209,6 +611,120 @@ impl ProcessInner {
         //     child_inner.parent = None;
         // }
         // self.children.clear();
+        self.proc_data.take()
+    }
+
+    pub fn fork(
+        &mut self,
+        parent: Weak<Process>,
+        page_table: PageTableContext,
+        kernel_stack: KernelStack,
+    ) -> ProcessInner {
+        let proc_data = self
+            .proc_data
+            .as_ref()
+            .expect("Process data empty. The process may be killed.")
+            .fork();
+
+        let parent_table = self.page_table.as_ref().unwrap();
+
+        if let Some(code_segments) = proc_data.code_segments.as_ref() {
+            for range in code_segments {
+                parent_table.share_cow(&page_table, *range);
+            }
+        }
+
+        if let Some(stack_segment) = proc_data.stack_segment {
+            parent_table.share_cow(&page_table, stack_segment);
+        }
+
+        parent_table.share_cow(&page_table, proc_data.heap.pages());
+
+        for vma in proc_data.vmas.read().iter() {
+            if vma.shared {
+                parent_table.share_pages(&page_table, vma.pages);
+                if let Some(key) = vma.shm {
+                    shm::share(key);
+                }
+            } else {
+                parent_table.share_cow(&page_table, vma.pages);
+            }
+        }
+
+        // child returns 0 from fork
+        let mut context = self.context;
+        context.set_rax(0);
+
+        ProcessInner {
+            name: self.name.clone(),
+            parent: Some(parent),
+            children: Vec::new(),
+            ticks_passed: 0,
+            status: ProgramStatus::Ready,
+            exit_code: None,
+            context,
+            page_table: Some(page_table),
+            proc_data: Some(proc_data),
+            signals: self.signals.fork(),
+            is_thread: false,
+            tracer: self.tracer,
+            exit_waiters: Vec::new(),
+            waiting_child: false,
+            waiting_sem: None,
+            pgid: self.pgid,
+            sid: self.sid,
+            stopped: false,
+            kernel_stack,
+        }
+    }
+
+    /// A thread running `entry(arg)` on `stack_top` in the same address space
+    pub fn thread(
+        &self,
+        parent: Weak<Process>,
+        entry: VirtAddr,
+        stack_top: VirtAddr,
+        arg: usize,
+        kernel_stack: KernelStack,
+    ) -> ProcessInner {
+        let proc_data = self
+            .proc_data
+            .as_ref()
+            .expect("Process data empty. The process may be killed.")
+            .thread();
+
+        let mut context = ProcessContext::default();
+        context.init_stack_frame(entry, stack_top);
+        context.set_rdi(arg);
+
+        ProcessInner {
+            name: self.name.clone(),
+            parent: Some(parent),
+            children: Vec::new(),
+            ticks_passed: 0,
+            status: ProgramStatus::Ready,
+            exit_code: None,
+            context,
+            page_table: self.page_table.clone(),
+            proc_data: Some(proc_data),
+            signals: self.signals.fork(),
+            is_thread: true,
+            tracer: self.tracer,
+            exit_waiters: Vec::new(),
+            waiting_child: false,
+            waiting_sem: None,
+            pgid: self.pgid,
+            sid: self.sid,
+            stopped: false,
+            kernel_stack,
+        }
+    }
+
+    pub fn handle_cow_page_fault(&self, addr: VirtAddr) -> bool {
+        self.page_table
+            .as_ref()
+            .map(|page_table| page_table.handle_cow_fault(addr))
+            .unwrap_or(false)
     }
 
     pub fn handle_stack_page_fault(&mut self, fault_addr:VirtAddr) -> bool {
@@ -236,7 +752,7 @@ impl ProcessInner {
         let frame_alloc = &mut *get_frame_alloc_for_sure();
         let page_table = self.page_table.as_mut().unwrap();
         let mut mapper = page_table.mapper();
-        let stack_bot = STACK_INIT_BOT - (pid - 1) * STACK_MAX_SIZE;
+        let stack_bot = stack_bot(pid);
         let code_segments = elf::load_elf(
             elf,
             *PHYSICAL_OFFSET.get().unwrap(),
@@ -252,7 +768,90 @@ impl ProcessInner {
         stack_bot
     }
 
+    /// Load `elf` and put `argv` and `envp` on the initial stack
+    ///
+    /// Fails with ENOMEM if the arguments do not fit on the initial stack,
+    /// nothing is mapped in that case, and with EFAULT if they cannot be
+    /// copied to it.
+    pub fn load_program(
+        &mut self,
+        elf: &ElfFile,
+        pid: u64,
+        argv: &[String],
+        envp: &[String],
+    ) -> Result<(), Errno> {
+        let entry = elf.header.pt2.entry_point();
+        let stack = ProgramStack::new(stack_bot(pid) + STACK_DEF_SIZE, entry, argv, envp)
+            .ok_or(Errno::ENOMEM)?;
+
+        self.load_elf(elf, pid);
+
+        let page_table = self.page_table.as_ref().unwrap();
+        if !page_table.copy_to(VirtAddr::new(stack.rsp), &stack.image) {
+            warn!("Failed to copy program arguments to the stack");
+            return Err(Errno::EFAULT);
+        }
+
+        self.context = ProcessContext::default();
+        self.init_stack_frame(VirtAddr::new_truncate(entry), VirtAddr::new(stack.rsp));
+        self.context.set_rdi(stack.argc);
+        self.context.set_rsi(stack.argv as usize);
+        self.context.set_rdx(stack.envp as usize);
 
+        Ok(())
+    }
+
+    /// Replace the process image with `elf`
+    ///
+    /// The pid, parent and resources are kept, the old code and stack are unmapped.
+    /// Fails with ENOMEM if the arguments do not fit, the old image is kept in
+    /// that case. A later failure leaves no image, and the process is killed.
+    pub fn exec(
+        &mut self,
+        name: &str,
+        elf: &ElfFile,
+        pid: u64,
+        argv: &[String],
+        envp: &[String],
+    ) -> Result<(), Errno> {
+        let entry = elf.header.pt2.entry_point();
+        if ProgramStack::new(stack_bot(pid) + STACK_DEF_SIZE, entry, argv, envp).is_none() {
+            return Err(Errno::ENOMEM);
+        }
+
+        let page_table = self.page_table.as_ref().unwrap();
+        let proc_data = self.proc_data.as_mut().unwrap();
+
+        if let Some(code_segments) = proc_data.code_segments.take() {
+            for range in code_segments {
+                page_table.unmap_pages(range);
+            }
+        }
+
+        if let Some(stack_segment) = proc_data.stack_segment.take() {
+            page_table.unmap_pages(stack_segment);
+        }
+
+        proc_data.heap.clean_up(page_table);
+        for vma in proc_data.vmas.write().drain(..) {
+            vma.release(page_table);
+        }
+
+        self.name = name.to_ascii_lowercase();
+        self.signals.exec();
+        let ret = self.load_program(elf, pid, argv, envp);
+        if ret.is_err() {
+            // delivered when the syscall returns
+            self.force_signal(Signal::SIGKILL);
+        }
+        ret
+    }
+}
+
+/// Bottom of the initial stack of process `pid`
+#[inline]
+fn stack_bot(pid: u64) -> u64 {
+    STACK_INIT_BOT - (pid - 1) * STACK_MAX_SIZE
 }
 
 impl core::ops::Deref for Process {
@@ -297,6 +896,10 @@ impl core::fmt::Debug for Process {
         );
         f.field("page_table", &inner.page_table);
         f.field("status", &inner.status);
+        f.field("is_thread", &inner.is_thread);
+        f.field("tracer", &inner.tracer);
+        f.field("pgid", &inner.pgid);
+        f.field("sid", &inner.sid);
         f.field("context", &inner.context);
         f.field("stack", &inner.proc_data.as_ref().map(|d| d.stack_segment));
         f.finish()
@@ -315,6 +918,9 @@ impl core::fmt::Display for Process {
             inner.ticks_passed,
             inner.status
         )?;
+        if inner.is_thread {
+            write!(f, " (thread)")?;
+        }
         Ok(())
     }
 }
@@ -34,6 +34,11 @@ pub fn print_processors() -> String {
     )
 }
 
+/// Number of processors running a process
+pub fn cpu_count() -> usize {
+    PROCESSORS.iter().filter(|p| !p.is_free()).count()
+}
+
 /// Processor holds the current process id
 pub struct Processor(AtomicU16);
 
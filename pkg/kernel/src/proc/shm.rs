@@ -0,0 +1,150 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use spin::Mutex;
+use syscall_def::Errno;
+use x86_64::structures::paging::{FrameAllocator, PhysFrame};
+
+use crate::memory::{get_frame_alloc_for_sure, physical_to_virtual, PAGE_SIZE};
+
+/// Shared memory segments by their user-chosen key
+static SEGMENTS: Mutex<BTreeMap<u32, ShmSegment>> = Mutex::new(BTreeMap::new());
+
+/// Frames of a shared memory segment
+///
+/// The segment holds one reference to each frame, every attached mapping
+/// holds another one, so the frames outlive any single process.
+#[derive(Debug)]
+struct ShmSegment {
+    frames: Vec<PhysFrame>,
+    /// Number of mappings of the segment
+    attached: usize,
+    /// Released on the last detach, and cannot be attached anymore
+    removed: bool,
+}
+
+impl ShmSegment {
+    fn new(count: usize) -> Option<Self> {
+        let mut segment = Self {
+            frames: Vec::with_capacity(count),
+            attached: 0,
+            removed: false,
+        };
+        // dropped before `segment`, which takes the lock again on failure
+        let mut frame_alloc = get_frame_alloc_for_sure();
+
+        for _ in 0..count {
+            let frame = frame_alloc.allocate_frame()?;
+            unsafe {
+                core::ptr::write_bytes(
+                    physical_to_virtual(frame.start_address().as_u64()) as *mut u8,
+                    0,
+                    PAGE_SIZE as usize,
+                );
+            }
+            segment.frames.push(frame);
+        }
+
+        Some(segment)
+    }
+}
+
+impl Drop for ShmSegment {
+    fn drop(&mut self) {
+        let mut frame_alloc = get_frame_alloc_for_sure();
+        for frame in self.frames.drain(..) {
+            unsafe { frame_alloc.release_frame(frame) };
+        }
+    }
+}
+
+/// Create the segment `key` of at least `size` bytes if it does not exist
+///
+/// Fails with `EINVAL` if an existing segment is smaller, with `EEXIST` if
+/// it is removed but still attached, or with `ENOMEM` if the frames run out.
+pub fn get(key: u32, size: usize) -> Result<(), Errno> {
+    let count = size.div_ceil(PAGE_SIZE as usize);
+    let mut segments = SEGMENTS.lock();
+
+    if let Some(segment) = segments.get(&key) {
+        return if segment.removed {
+            Err(Errno::EEXIST)
+        } else if count <= segment.frames.len() {
+            Ok(())
+        } else {
+            Err(Errno::EINVAL)
+        };
+    }
+
+    if count == 0 {
+        return Err(Errno::EINVAL);
+    }
+
+    match ShmSegment::new(count) {
+        Some(segment) => {
+            trace!("Shm: new segment <{:#x}> of {} pages", key, count);
+            segments.insert(key, segment);
+            Ok(())
+        }
+        None => {
+            warn!("Shm: no frames for segment <{:#x}>", key);
+            Err(Errno::ENOMEM)
+        }
+    }
+}
+
+/// Count a new mapping of the segment `key`, returns its frames
+pub fn attach(key: u32) -> Option<Vec<PhysFrame>> {
+    let mut segments = SEGMENTS.lock();
+    let segment = segments.get_mut(&key).filter(|segment| !segment.removed)?;
+    segment.attached += 1;
+    Some(segment.frames.clone())
+}
+
+/// Count a mapping copied into a forked process, even if the segment is removed
+pub fn share(key: u32) {
+    if let Some(segment) = SEGMENTS.lock().get_mut(&key) {
+        segment.attached += 1;
+    }
+}
+
+/// Remove the segment `key`, it is released now if unused, else on the last detach
+pub fn remove(key: u32) -> Result<(), Errno> {
+    let segment = {
+        let mut segments = SEGMENTS.lock();
+        let segment = segments
+            .get_mut(&key)
+            .filter(|segment| !segment.removed)
+            .ok_or(Errno::ENOENT)?;
+
+        if segment.attached > 0 {
+            segment.removed = true;
+            return Ok(());
+        }
+
+        segments.remove(&key)
+    };
+
+    trace!("Shm: release segment <{:#x}>", key);
+    drop(segment);
+    Ok(())
+}
+
+/// Drop a mapping of the segment `key`, the last one releases the segment
+pub fn detach(key: u32) {
+    let segment = {
+        let mut segments = SEGMENTS.lock();
+        let Some(segment) = segments.get_mut(&key) else {
+            return;
+        };
+
+        segment.attached -= 1;
+        if segment.attached > 0 {
+            return;
+        }
+
+        segments.remove(&key)
+    };
+
+    trace!("Shm: release segment <{:#x}>", key);
+    drop(segment);
+}
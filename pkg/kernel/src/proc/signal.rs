@@ -0,0 +1,183 @@
+use syscall_def::{Signal, SIG_DFL, SIG_IGN};
+use x86_64::VirtAddr;
+
+use super::ProcessContext;
+
+/// Signal numbers are below 32, one pending bit each
+const SIG_NUM: usize = 32;
+
+/// Pending bits of the signals that stop a process
+const STOP_MASK: u32 = (1 << Signal::SIGSTOP as usize) | (1 << Signal::SIGTSTP as usize);
+
+/// Disposition of a signal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SigHandler {
+    #[default]
+    Default,
+    Ignore,
+    Handler(u64),
+}
+
+impl SigHandler {
+    pub fn from_raw(handler: usize) -> Self {
+        match handler {
+            SIG_DFL => SigHandler::Default,
+            SIG_IGN => SigHandler::Ignore,
+            addr => SigHandler::Handler(addr as u64),
+        }
+    }
+
+    pub fn as_raw(&self) -> usize {
+        match self {
+            SigHandler::Default => SIG_DFL,
+            SigHandler::Ignore => SIG_IGN,
+            SigHandler::Handler(addr) => *addr as usize,
+        }
+    }
+}
+
+/// Default action of a delivered signal that the kernel has to carry out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigAction {
+    Terminate(Signal),
+    Stop(Signal),
+}
+
+/// Exit code of a process terminated by `sig`
+#[inline]
+pub fn exit_code(sig: Signal) -> isize {
+    -(sig as isize)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SignalState {
+    pending: u32,
+    handlers: [SigHandler; SIG_NUM],
+    /// User function that calls the handler and then `SigReturn`
+    trampoline: u64,
+    /// Context interrupted by the running handler
+    saved: Option<ProcessContext>,
+}
+
+impl SignalState {
+    /// Signal state of a forked child, the handlers are kept
+    pub fn fork(&self) -> Self {
+        Self {
+            pending: 0,
+            ..self.clone()
+        }
+    }
+
+    /// User handlers are gone with the old image, ignored signals stay ignored
+    pub fn exec(&mut self) {
+        for handler in self.handlers.iter_mut() {
+            if let SigHandler::Handler(_) = handler {
+                *handler = SigHandler::Default;
+            }
+        }
+        self.trampoline = 0;
+        self.saved = None;
+    }
+
+    /// Make `sig` pending, a stop and a continue cancel each other
+    pub fn raise(&mut self, sig: Signal) {
+        match sig {
+            Signal::SIGCONT => self.pending &= !STOP_MASK,
+            Signal::SIGSTOP | Signal::SIGTSTP => self.pending &= !(1 << Signal::SIGCONT as usize),
+            _ => (),
+        }
+        self.pending |= 1 << sig as usize;
+    }
+
+    /// Raise `sig` for a fault of the running process
+    ///
+    /// If the signal is ignored or its handler cannot run right now, the
+    /// default action is restored, since returning would fault again.
+    pub fn force(&mut self, sig: Signal) {
+        let handler = &mut self.handlers[sig as usize];
+        match handler {
+            SigHandler::Ignore => *handler = SigHandler::Default,
+            SigHandler::Handler(_) if self.saved.is_some() => *handler = SigHandler::Default,
+            _ => (),
+        }
+        self.raise(sig);
+    }
+
+    /// Set the handler of `sig`, returns the previous one
+    ///
+    /// The handlers of SIGKILL and SIGSTOP cannot be changed.
+    pub fn set_handler(
+        &mut self,
+        sig: Signal,
+        handler: SigHandler,
+        trampoline: u64,
+    ) -> Option<SigHandler> {
+        if matches!(sig, Signal::SIGKILL | Signal::SIGSTOP) {
+            return None;
+        }
+
+        if let SigHandler::Handler(_) = handler {
+            self.trampoline = trampoline;
+        }
+
+        Some(core::mem::replace(&mut self.handlers[sig as usize], handler))
+    }
+
+    /// Deliver the pending signals before returning to user mode
+    ///
+    /// A user handler is entered by rewriting `context`, while it runs other
+    /// handled signals are kept pending. Returns the default action if it
+    /// terminates or stops the process.
+    pub fn deliver(&mut self, context: &mut ProcessContext) -> Option<SigAction> {
+        for num in 0..SIG_NUM {
+            if self.pending & (1 << num) == 0 {
+                continue;
+            }
+
+            let Ok(sig) = Signal::try_from(num) else {
+                self.pending &= !(1 << num);
+                continue;
+            };
+
+            let handler = self.handlers[num];
+            if matches!(handler, SigHandler::Handler(_)) && self.saved.is_some() {
+                continue;
+            }
+
+            self.pending &= !(1 << num);
+
+            match handler {
+                SigHandler::Ignore => continue,
+                SigHandler::Default => match sig {
+                    // the process was resumed when the signal was sent
+                    Signal::SIGCHLD | Signal::SIGCONT => continue,
+                    Signal::SIGSTOP | Signal::SIGTSTP => return Some(SigAction::Stop(sig)),
+                    _ => return Some(SigAction::Terminate(sig)),
+                },
+                SigHandler::Handler(addr) => {
+                    self.saved = Some(*context);
+
+                    // skip the red zone, and align as if the trampoline was called
+                    let stack_top = ((context.stack_frame.stack_pointer.as_u64() - 128) & !0xf) - 8;
+                    context.redirect(VirtAddr::new(self.trampoline), VirtAddr::new(stack_top));
+                    context.set_rdi(sig as usize);
+                    context.set_rsi(addr as usize);
+                    return None;
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Restore the context interrupted by the handler
+    pub fn sigreturn(&mut self, context: &mut ProcessContext) -> bool {
+        match self.saved.take() {
+            Some(saved) => {
+                context.save(&saved);
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec::Vec;
+use spin::Mutex;
+
+use super::ProcessId;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct SemaphoreId(u32);
+
+impl SemaphoreId {
+    pub fn new(key: u32) -> Self {
+        Self(key)
+    }
+}
+
+/// Outcome of a semaphore operation for the process manager
+#[derive(Debug, Eq, PartialEq)]
+pub enum SemaphoreResult {
+    Ok,
+    NotExist,
+    /// The caller has to be blocked
+    Block(ProcessId),
+    /// The process has been handed the semaphore and has to be woken up
+    WakeUp(ProcessId),
+}
+
+/// Counting semaphore with a FIFO wait queue
+#[derive(Debug)]
+pub struct Semaphore {
+    count: usize,
+    wait_queue: VecDeque<ProcessId>,
+}
+
+impl Semaphore {
+    pub fn new(value: usize) -> Self {
+        Self {
+            count: value,
+            wait_queue: VecDeque::new(),
+        }
+    }
+
+    /// Take the semaphore, or queue `pid` if it is not available
+    pub fn wait(&mut self, pid: ProcessId) -> SemaphoreResult {
+        if self.count == 0 {
+            self.wait_queue.push_back(pid);
+            SemaphoreResult::Block(pid)
+        } else {
+            self.count -= 1;
+            SemaphoreResult::Ok
+        }
+    }
+
+    /// Take `pid` out of the wait queue, returns whether it was queued
+    pub fn cancel(&mut self, pid: ProcessId) -> bool {
+        let len = self.wait_queue.len();
+        self.wait_queue.retain(|&waiter| waiter != pid);
+        self.wait_queue.len() != len
+    }
+
+    /// Release the semaphore, it goes straight to the first waiter if any
+    pub fn signal(&mut self) -> SemaphoreResult {
+        match self.wait_queue.pop_front() {
+            Some(pid) => SemaphoreResult::WakeUp(pid),
+            None => {
+                self.count += 1;
+                SemaphoreResult::Ok
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SemaphoreSet {
+    sems: BTreeMap<SemaphoreId, Mutex<Semaphore>>,
+}
+
+impl SemaphoreSet {
+    /// Create the semaphore `key`, returns `false` if it already exists
+    pub fn insert(&mut self, key: u32, value: usize) -> bool {
+        trace!("Sem Insert: <{:#x}>{}", key, value);
+
+        let key = SemaphoreId::new(key);
+        if self.sems.contains_key(&key) {
+            return false;
+        }
+
+        self.sems.insert(key, Mutex::new(Semaphore::new(value)));
+        true
+    }
+
+    /// Remove the semaphore `key`, returns the processes still waiting on it
+    pub fn remove(&mut self, key: u32) -> Option<Vec<ProcessId>> {
+        trace!("Sem Remove: <{:#x}>", key);
+
+        self.sems
+            .remove(&SemaphoreId::new(key))
+            .map(|sem| sem.into_inner().wait_queue.into())
+    }
+
+    pub fn wait(&self, key: u32, pid: ProcessId) -> SemaphoreResult {
+        match self.sems.get(&SemaphoreId::new(key)) {
+            Some(sem) => sem.lock().wait(pid),
+            None => SemaphoreResult::NotExist,
+        }
+    }
+
+    /// Stop `pid` from waiting on `key`, if both still exist
+    pub fn cancel(&self, key: u32, pid: ProcessId) -> bool {
+        self.sems
+            .get(&SemaphoreId::new(key))
+            .is_some_and(|sem| sem.lock().cancel(pid))
+    }
+
+    pub fn signal(&self, key: u32) -> SemaphoreResult {
+        match self.sems.get(&SemaphoreId::new(key)) {
+            Some(sem) => sem.lock().signal(),
+            None => SemaphoreResult::NotExist,
+        }
+    }
+}
+
+impl core::fmt::Display for Semaphore {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "Semaphore({}) {:?}", self.count, self.wait_queue)
+    }
+}
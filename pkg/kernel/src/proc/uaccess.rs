@@ -0,0 +1,226 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::mem::{size_of, size_of_val, MaybeUninit};
+use core::ptr::copy_nonoverlapping;
+
+use syscall_def::{AppInfo, ProcessInfo, ProgramArgs, SysInfo, TimeSpec, TraceRecord};
+use x86_64::{
+    structures::{
+        idt::PageFaultErrorCode,
+        paging::{mapper::*, PageTableFlags},
+    },
+    VirtAddr,
+};
+
+use super::PageTableContext;
+use crate::memory::{physical_to_virtual, PAGE_SIZE};
+
+/// End of the lower half of the address space, which belongs to user processes
+pub const USER_SPACE_END: u64 = 0x0000_8000_0000_0000;
+
+/// Types that are valid for any bit pattern, and can be copied from user memory
+///
+/// # Safety
+///
+/// Every bit pattern of `size_of::<Self>()` bytes must be a valid value,
+/// and the type must have no padding, so that it can be copied out as well.
+pub unsafe trait Plain: Copy {}
+
+unsafe impl Plain for u8 {}
+unsafe impl Plain for u16 {}
+unsafe impl Plain for u32 {}
+unsafe impl Plain for u64 {}
+unsafe impl Plain for usize {}
+unsafe impl Plain for isize {}
+unsafe impl<T: Plain, const N: usize> Plain for [T; N] {}
+unsafe impl Plain for ProgramArgs {}
+unsafe impl Plain for TraceRecord {}
+unsafe impl Plain for ProcessInfo {}
+unsafe impl Plain for AppInfo {}
+unsafe impl Plain for SysInfo {}
+unsafe impl Plain for TimeSpec {}
+
+/// A pointer to a `T` in the memory of the current process
+#[derive(Debug, Clone, Copy)]
+pub struct UserPtr<T> {
+    addr: usize,
+    _marker: PhantomData<*mut T>,
+}
+
+impl<T: Plain> UserPtr<T> {
+    pub fn new(addr: usize) -> Self {
+        Self {
+            addr,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.addr == 0
+    }
+
+    /// Copy the value in, returns `None` if it is not readable by the process
+    pub fn read(&self) -> Option<T> {
+        let mut value = MaybeUninit::<T>::uninit();
+        let buf = unsafe {
+            core::slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, size_of::<T>())
+        };
+
+        if copy_in(self.addr, buf) {
+            Some(unsafe { value.assume_init() })
+        } else {
+            None
+        }
+    }
+
+    /// Copy `value` out, returns `false` if it is not writable by the process
+    pub fn write(&self, value: T) -> bool {
+        let data = unsafe {
+            core::slice::from_raw_parts(&value as *const T as *const u8, size_of::<T>())
+        };
+        copy_out(self.addr, data)
+    }
+
+    /// Copy `values` to the array starting here, returns `false` if it is
+    /// not writable by the process
+    pub fn write_slice(&self, values: &[T]) -> bool {
+        let data = unsafe {
+            core::slice::from_raw_parts(values.as_ptr() as *const u8, size_of_val(values))
+        };
+        copy_out(self.addr, data)
+    }
+}
+
+/// A buffer of bytes in the memory of the current process
+#[derive(Debug, Clone, Copy)]
+pub struct UserSlice {
+    addr: usize,
+    len: usize,
+}
+
+impl UserSlice {
+    pub fn new(addr: usize, len: usize) -> Self {
+        Self { addr, len }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Copy the buffer in, returns `None` if it is not readable by the process
+    pub fn read_to_vec(&self) -> Option<Vec<u8>> {
+        let mut buf = vec![0; self.len];
+        copy_in(self.addr, &mut buf).then_some(buf)
+    }
+
+    /// Copy the buffer in as UTF-8 text
+    pub fn read_to_string(&self) -> Option<String> {
+        String::from_utf8(self.read_to_vec()?).ok()
+    }
+
+    /// Copy `data` to the start of the buffer
+    ///
+    /// Returns `false` if `data` does not fit, or the buffer is not writable
+    /// by the process.
+    pub fn write(&self, data: &[u8]) -> bool {
+        data.len() <= self.len && copy_out(self.addr, data)
+    }
+}
+
+/// Copy `[addr, addr + buf.len())` of the current process into `buf`
+fn copy_in(addr: usize, buf: &mut [u8]) -> bool {
+    for_each_chunk(addr, buf.len(), false, |phys, offset, len| unsafe {
+        copy_nonoverlapping(phys as *const u8, buf.as_mut_ptr().add(offset), len);
+    })
+}
+
+/// Copy `data` to `[addr, addr + data.len())` of the current process
+fn copy_out(addr: usize, data: &[u8]) -> bool {
+    for_each_chunk(addr, data.len(), true, |phys, offset, len| unsafe {
+        copy_nonoverlapping(data.as_ptr().add(offset), phys as *mut u8, len);
+    })
+}
+
+/// Walk `[addr, addr + len)` page by page through the physical memory mapping
+///
+/// `f` gets the kernel address of each chunk, its offset in the range and
+/// its length. Returns `false` at the first page the process cannot access.
+fn for_each_chunk(addr: usize, len: usize, write: bool, mut f: impl FnMut(u64, usize, usize)) -> bool {
+    match (addr as u64).checked_add(len as u64) {
+        Some(end) if end <= USER_SPACE_END => (),
+        _ => return false,
+    }
+
+    if len == 0 {
+        return true;
+    }
+
+    // the syscall runs in the address space of the caller
+    let page_table = PageTableContext::new();
+    let mut offset = 0;
+
+    while offset < len {
+        let cur = VirtAddr::new((addr + offset) as u64);
+        let Some(phys) = translate(&page_table, cur, write) else {
+            debug!("Invalid user {} at {:#x}", if write { "write" } else { "read" }, cur);
+            return false;
+        };
+
+        let in_page = (PAGE_SIZE - (cur.as_u64() & (PAGE_SIZE - 1))) as usize;
+        let chunk = in_page.min(len - offset);
+
+        f(physical_to_virtual(phys), offset, chunk);
+        offset += chunk;
+    }
+
+    true
+}
+
+/// Physical address of `addr` if the process can access it
+///
+/// Pages that are not populated yet, or copy-on-write pages about to be
+/// written, are resolved as if the process itself had faulted on them.
+fn translate(page_table: &PageTableContext, addr: VirtAddr, write: bool) -> Option<u64> {
+    let mut err_code = PageFaultErrorCode::USER_MODE;
+    if write {
+        err_code |= PageFaultErrorCode::CAUSED_BY_WRITE;
+    }
+
+    match lookup(page_table, addr, write) {
+        Ok(phys) => return Some(phys),
+        Err(false) => (),
+        Err(true) => err_code |= PageFaultErrorCode::PROTECTION_VIOLATION,
+    }
+
+    if !super::handle_page_fault(addr, err_code) {
+        return None;
+    }
+
+    lookup(page_table, addr, write).ok()
+}
+
+/// Look up `addr` in `page_table`, the error tells whether the page is present
+fn lookup(page_table: &PageTableContext, addr: VirtAddr, write: bool) -> Result<u64, bool> {
+    match page_table.mapper().translate(addr) {
+        TranslateResult::Mapped {
+            frame,
+            offset,
+            flags,
+        } => {
+            if flags.contains(PageTableFlags::USER_ACCESSIBLE)
+                && (!write || flags.contains(PageTableFlags::WRITABLE))
+            {
+                Ok(frame.start_address().as_u64() + offset)
+            } else {
+                Err(true)
+            }
+        }
+        _ => Err(false),
+    }
+}
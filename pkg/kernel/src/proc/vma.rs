@@ -0,0 +1,66 @@
+use syscall_def::{MAP_SHARED, PROT_EXEC, PROT_READ, PROT_WRITE};
+
+use super::{shm, PageTableContext};
+use x86_64::{
+    structures::paging::{page::PageRange, Page, PageTableFlags},
+    VirtAddr,
+};
+
+/// Address range for memory mapped regions, above the heap
+pub const MMAP_START: u64 = 0x5000_0000_0000;
+pub const MMAP_END: u64 = 0x6000_0000_0000;
+
+/// A memory mapped region of a process
+#[derive(Debug, Clone, Copy)]
+pub struct Vma {
+    pub pages: PageRange,
+    /// Flags of the pages, without `PRESENT` the region cannot be accessed
+    pub flags: PageTableFlags,
+    /// Shared regions are mapped eagerly and not copied on fork
+    pub shared: bool,
+    /// Key of the attached shared memory segment
+    pub shm: Option<u32>,
+}
+
+impl Vma {
+    /// Build a region from the `prot | flags` bits of `Syscall::Mmap`
+    pub fn new(pages: PageRange, bits: usize) -> Self {
+        let mut flags = PageTableFlags::empty();
+
+        if bits & (PROT_READ | PROT_WRITE | PROT_EXEC) != 0 {
+            flags |= PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE;
+        }
+        if bits & PROT_WRITE != 0 {
+            flags |= PageTableFlags::WRITABLE;
+        }
+        if bits & PROT_EXEC == 0 {
+            flags |= PageTableFlags::NO_EXECUTE;
+        }
+
+        Self {
+            pages,
+            flags,
+            shared: bits & MAP_SHARED != 0,
+            shm: None,
+        }
+    }
+
+    #[inline]
+    pub fn contains(&self, addr: VirtAddr) -> bool {
+        let page = Page::containing_address(addr);
+        self.pages.start <= page && page < self.pages.end
+    }
+
+    #[inline]
+    pub fn overlaps(&self, pages: PageRange) -> bool {
+        self.pages.start < pages.end && pages.start < self.pages.end
+    }
+
+    /// Unmap the region from `page_table`, detaching its shared memory segment
+    pub fn release(&self, page_table: &PageTableContext) {
+        page_table.unmap_pages(self.pages);
+        if let Some(key) = self.shm {
+            shm::detach(key);
+        }
+    }
+}
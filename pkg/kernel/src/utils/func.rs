@@ -14,9 +14,7 @@ test() -> ! {
             count = 0;
             print!("\r{:-6} => Tick!", id);
         }
-        unsafe {
-            x86_64::instructions::hlt();
-        }
+        x86_64::instructions::hlt();
     }
 }
 
@@ -28,12 +26,9 @@ fn huge_stack() {
     let mut stack= [0u64; 128];
 
     println!("creating stack...");
-    // for (idx, item) in stack.iter_mut().enumerate() {
-    //     *item = idx as u64;
-    // }
-    for i in 0..stack.len() {
-        stack[i] = i as u64;
-        println!("{:#05x} == {:#05x}", i, stack[i]);
+    for (idx, item) in stack.iter_mut().enumerate() {
+        *item = idx as u64;
+        println!("{:#05x} == {:#05x}", idx, *item);
     }
     // for i in 0..stack.len() / 256 {
     //     println!("{:#05x} == {:#05x}", i * 256, stack[i * 256]);
@@ -4,14 +4,12 @@ pub fn init() {
     static LOGGER: Logger = Logger;
     log::set_logger(&LOGGER).unwrap();
     log::set_max_level(match option_env!("LOG_LEVEL") {
-        // Some("error") => LevelFilter::Error,
-        // Some("warn") => LevelFilter::Warn,
-        // Some("info") => LevelFilter::Info,
-        // Some("debug") => LevelFilter::Debug,
-        // Some("trace") => LevelFilter::Trace,
-        _ => LevelFilter::Trace,  // 默认日志级别为 info
+        Some("error") => LevelFilter::Error,
+        Some("warn") => LevelFilter::Warn,
+        Some("info") => LevelFilter::Info,
+        Some("debug") => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
     });
-    log::set_max_level(LevelFilter::Trace);
     // FIXME: Configure the logger
     info!("Current log level: {}", log::max_level());
 
@@ -32,5 +30,9 @@ impl log::Log for Logger {
         }
     }
 
-    fn flush(&self) {}
+    fn flush(&self) {
+        if let Some(mut serial) = crate::drivers::serial::get_serial() {
+            serial.flush();
+        }
+    }
 }
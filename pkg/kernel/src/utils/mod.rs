@@ -6,7 +6,6 @@ mod regs;
 // pub mod clock;
 pub mod func;
 pub mod logger;
-use alloc::format;
 pub use macros::*;
 pub use manager::get_process_manager;
 pub use regs::*;
@@ -51,15 +50,9 @@ __  __      __  _____            ____  _____
 // }
 
 pub fn wait(pid: ProcessId) {
-    loop {
-        // 尝试获取进程的退出状态
-        // let exit_code = get_process_manager().get_proc(&pid).unwrap().read().exit_code();
-        let exit_code = get_process_manager().get_exit_code(pid);
-        if exit_code.is_none() {
-            x86_64::instructions::hlt();
-        } else {
-            break;
-        }
+    // children of the kernel process are reaped as soon as they exit
+    while still_alive(pid) {
+        x86_64::instructions::hlt();
     }
 }
 const SHORT_UNITS: [&str; 4] = ["B", "K", "M", "G"];
@@ -1,5 +1,13 @@
-use alloc::{collections::BTreeMap, string::String};
+use alloc::{
+    collections::{BTreeMap, VecDeque},
+    string::String,
+    sync::Arc,
+    vec::Vec,
+};
 use spin::Mutex;
+use syscall_def::Errno;
+
+use crate::proc::ProcessId;
 
 #[derive(Debug, Clone)]
 pub enum StdIO {
@@ -8,9 +16,12 @@ pub enum StdIO {
     Stderr,
 }
 
-#[derive(Debug)]
+/// Max number of open file descriptors per process
+pub const MAX_FDS: usize = 32;
+
+#[derive(Debug, Clone)]
 pub struct ResourceSet {
-    pub handles: BTreeMap<u8, Mutex<Resource>>,
+    pub handles: BTreeMap<u8, Arc<Mutex<Resource>>>,
 }
 
 impl Default for ResourceSet {
@@ -28,67 +39,245 @@ impl Default for ResourceSet {
 }
 
 impl ResourceSet {
-    pub fn open(&mut self, res: Resource) -> u8 {
-        let fd = self.handles.len() as u8;
-        self.handles.insert(fd, Mutex::new(res));
-        fd
+    /// Get the lowest free fd, `None` if the fd limit is reached
+    fn free_fd(&self) -> Option<u8> {
+        (0..MAX_FDS as u8).find(|fd| !self.handles.contains_key(fd))
+    }
+
+    pub fn open(&mut self, res: Resource) -> Option<u8> {
+        let fd = self.free_fd()?;
+        self.handles.insert(fd, Arc::new(Mutex::new(res)));
+        Some(fd)
     }
 
     pub fn close(&mut self, fd: u8) -> bool {
         self.handles.remove(&fd).is_some()
     }
 
-    pub fn read(&self, fd: u8, buf: &mut [u8]) -> isize {
-        if let Some(count) = self.handles.get(&fd).and_then(|h| h.lock().read(buf)) {
-            count as isize
-        } else {
-            -1
-        }
+    /// Duplicate `fd` to the lowest free fd
+    pub fn dup(&mut self, fd: u8) -> Result<u8, Errno> {
+        let handle = self.handles.get(&fd).ok_or(Errno::EBADF)?.clone();
+        let new_fd = self.free_fd().ok_or(Errno::EMFILE)?;
+        self.handles.insert(new_fd, handle);
+        Ok(new_fd)
     }
 
-    pub fn write(&self, fd: u8, buf: &[u8]) -> isize {
-        if let Some(count) = self.handles.get(&fd).and_then(|h| h.lock().write(buf)) {
-            count as isize
-        } else {
-            -1
+    /// Duplicate `fd` to `new_fd`, closing `new_fd` first if it is open
+    pub fn dup2(&mut self, fd: u8, new_fd: u8) -> Result<u8, Errno> {
+        let handle = self.handles.get(&fd).ok_or(Errno::EBADF)?.clone();
+        if new_fd as usize >= MAX_FDS {
+            return Err(Errno::EBADF);
         }
+        self.handles.insert(new_fd, handle);
+        Ok(new_fd)
+    }
+
+    /// Register `pid` to be woken up when `fd` becomes readable,
+    /// returns `false` if reading `fd` would not block.
+    pub fn wait_read(&self, fd: u8, pid: ProcessId) -> bool {
+        self.handles
+            .get(&fd)
+            .is_some_and(|h| h.lock().wait_read(pid))
+    }
+
+    /// Register `pid` to be woken up when `fd` becomes writable,
+    /// returns `false` if writing `fd` would not block.
+    pub fn wait_write(&self, fd: u8, pid: ProcessId) -> bool {
+        self.handles
+            .get(&fd)
+            .is_some_and(|h| h.lock().wait_write(pid))
+    }
+
+    pub fn read(&self, fd: u8, buf: &mut [u8]) -> Result<usize, Errno> {
+        self.handles.get(&fd).ok_or(Errno::EBADF)?.lock().read(buf)
+    }
+
+    pub fn write(&self, fd: u8, buf: &[u8]) -> Result<usize, Errno> {
+        self.handles.get(&fd).ok_or(Errno::EBADF)?.lock().write(buf)
     }
 }
 
 #[derive(Debug)]
 pub enum Resource {
     Console(StdIO),
+    Pipe(Pipe),
     Null,
 }
 
 impl Resource {
-    pub fn read(&mut self, buf: &mut [u8]) -> Option<usize> {
+    /// Open a device by path
+    pub fn open(path: &str) -> Option<Self> {
+        match path {
+            "/dev/null" => Some(Resource::Null),
+            "/dev/stdin" => Some(Resource::Console(StdIO::Stdin)),
+            "/dev/stdout" => Some(Resource::Console(StdIO::Stdout)),
+            "/dev/stderr" => Some(Resource::Console(StdIO::Stderr)),
+            _ => None,
+        }
+    }
+
+    pub fn wait_read(&mut self, pid: ProcessId) -> bool {
+        match self {
+            Resource::Pipe(pipe) => pipe.wait_read(pid),
+            _ => false,
+        }
+    }
+
+    pub fn wait_write(&mut self, pid: ProcessId) -> bool {
+        match self {
+            Resource::Pipe(pipe) => pipe.wait_write(pid),
+            _ => false,
+        }
+    }
+
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, Errno> {
         match self {
             Resource::Console(stdio) => match stdio {
                 StdIO::Stdin => {
                     // FIXME: just read from kernel input buffer
-                    Some(0)
+                    Ok(0)
                 }
-                _ => None,
+                _ => Err(Errno::EBADF),
             },
-            Resource::Null => Some(0),
+            Resource::Pipe(pipe) => pipe.read(buf),
+            Resource::Null => Ok(0),
         }
     }
 
-    pub fn write(&mut self, buf: &[u8]) -> Option<usize> {
+    pub fn write(&mut self, buf: &[u8]) -> Result<usize, Errno> {
         match self {
             Resource::Console(stdio) => match *stdio {
-                StdIO::Stdin => None,
+                StdIO::Stdin => Err(Errno::EBADF),
                 StdIO::Stdout => {
                     print!("{}", String::from_utf8_lossy(buf));
-                    Some(buf.len())
+                    Ok(buf.len())
                 }
                 StdIO::Stderr => {
                     warn!("{}", String::from_utf8_lossy(buf));
-                    Some(buf.len())
+                    Ok(buf.len())
                 }
             },
-            Resource::Null => Some(buf.len()),
+            Resource::Pipe(pipe) => pipe.write(buf),
+            Resource::Null => Ok(buf.len()),
+        }
+    }
+}
+
+/// Capacity of the pipe ring buffer
+pub const PIPE_BUF_SIZE: usize = 4096;
+
+#[derive(Debug)]
+struct PipeBuffer {
+    buf: VecDeque<u8>,
+    readers: usize,
+    writers: usize,
+    /// Processes blocked on this pipe
+    waiting: Vec<ProcessId>,
+}
+
+impl PipeBuffer {
+    fn wake_all(&mut self) {
+        for pid in self.waiting.drain(..) {
+            crate::proc::wake_up(pid);
+        }
+    }
+}
+
+/// One end of an anonymous pipe
+#[derive(Debug)]
+pub struct Pipe {
+    buffer: Arc<Mutex<PipeBuffer>>,
+    writable: bool,
+}
+
+impl Pipe {
+    /// Create a pipe, returns the read end and the write end
+    pub fn new() -> (Pipe, Pipe) {
+        let buffer = Arc::new(Mutex::new(PipeBuffer {
+            buf: VecDeque::with_capacity(PIPE_BUF_SIZE),
+            readers: 1,
+            writers: 1,
+            waiting: Vec::new(),
+        }));
+
+        (
+            Pipe {
+                buffer: buffer.clone(),
+                writable: false,
+            },
+            Pipe {
+                buffer,
+                writable: true,
+            },
+        )
+    }
+
+    fn wait_read(&self, pid: ProcessId) -> bool {
+        let mut pipe = self.buffer.lock();
+        if self.writable || !pipe.buf.is_empty() || pipe.writers == 0 {
+            return false;
+        }
+        pipe.waiting.push(pid);
+        true
+    }
+
+    fn wait_write(&self, pid: ProcessId) -> bool {
+        let mut pipe = self.buffer.lock();
+        if !self.writable || pipe.buf.len() < PIPE_BUF_SIZE || pipe.readers == 0 {
+            return false;
+        }
+        pipe.waiting.push(pid);
+        true
+    }
+
+    /// Read from the pipe, returns `Ok(0)` on EOF
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Errno> {
+        if self.writable {
+            return Err(Errno::EBADF);
+        }
+
+        let mut pipe = self.buffer.lock();
+        let count = buf.len().min(pipe.buf.len());
+        for (dst, src) in buf.iter_mut().zip(pipe.buf.drain(..count)) {
+            *dst = src;
+        }
+
+        if count > 0 {
+            pipe.wake_all();
+        }
+        Ok(count)
+    }
+
+    /// Write to the pipe, fails if every read end is closed
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Errno> {
+        if !self.writable {
+            return Err(Errno::EBADF);
+        }
+
+        let mut pipe = self.buffer.lock();
+        if pipe.readers == 0 {
+            return Err(Errno::EPIPE);
+        }
+
+        let count = buf.len().min(PIPE_BUF_SIZE - pipe.buf.len());
+        pipe.buf.extend(&buf[..count]);
+
+        if count > 0 {
+            pipe.wake_all();
+        }
+        Ok(count)
+    }
+}
+
+impl Drop for Pipe {
+    fn drop(&mut self) {
+        let mut pipe = self.buffer.lock();
+        if self.writable {
+            pipe.writers -= 1;
+        } else {
+            pipe.readers -= 1;
         }
+        // blocked readers get EOF, blocked writers get an error
+        pipe.wake_all();
     }
 }
@@ -1,16 +1,73 @@
-pub struct KernelAllocator;
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr::{self, NonNull};
 
-unsafe impl alloc::alloc::GlobalAlloc for KernelAllocator {
-    unsafe fn alloc(&self, layout: core::alloc::Layout) -> *mut u8 {
-        crate::sys_allocate(&layout)
+use linked_list_allocator::LockedHeap;
+
+/// The heap grows by at least this many bytes at a time
+const HEAP_GROW_SIZE: usize = 64 * 1024;
+const PAGE_SIZE: usize = 4096;
+
+/// Allocator on the process heap, which is grown with `sys_brk` when full
+pub struct BrkAllocator {
+    heap: LockedHeap,
+}
+
+impl BrkAllocator {
+    pub const fn empty() -> Self {
+        Self {
+            heap: LockedHeap::empty(),
+        }
+    }
+}
+
+/// Extend the heap by at least `min` bytes
+fn grow(heap: &mut linked_list_allocator::Heap, min: usize) -> bool {
+    let size = min.max(HEAP_GROW_SIZE).next_multiple_of(PAGE_SIZE);
+
+    if heap.size() == 0 {
+        let Ok(base) = crate::sys_brk(None) else {
+            return false;
+        };
+        if crate::sys_brk(Some(base + size)).is_err() {
+            return false;
+        }
+        unsafe { heap.init(base as *mut u8, size) };
+    } else {
+        let top = heap.top() as usize;
+        if crate::sys_brk(Some(top + size)).is_err() {
+            return false;
+        }
+        unsafe { heap.extend(size) };
     }
-    unsafe fn dealloc(&self, ptr: *mut u8, layout: core::alloc::Layout) {
-        crate::sys_deallocate(ptr, &layout);
+
+    true
+}
+
+unsafe impl GlobalAlloc for BrkAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let mut heap = self.heap.lock();
+
+        loop {
+            if let Ok(ptr) = heap.allocate_first_fit(layout) {
+                return ptr.as_ptr();
+            }
+
+            // room for the alignment padding and the allocator's bookkeeping
+            if !grow(&mut heap, layout.size() + layout.align() * 2) {
+                return ptr::null_mut();
+            }
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if let Some(ptr) = NonNull::new(ptr) {
+            self.heap.lock().deallocate(ptr, layout);
+        }
     }
 }
 
 #[global_allocator]
-static ALLOCATOR: KernelAllocator = KernelAllocator;
+static ALLOCATOR: BrkAllocator = BrkAllocator::empty();
 
 #[cfg(not(test))]
 #[alloc_error_handler]
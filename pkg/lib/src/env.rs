@@ -0,0 +1,44 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{sys_get_env, sys_set_env, Errno};
+
+/// Collect the program arguments passed on the initial stack
+///
+/// # Safety
+///
+/// `argv` must point to `argc` valid NUL-terminated strings,
+/// which is guaranteed for the values passed to `_start` by the kernel.
+pub unsafe fn args(argc: usize, argv: *const *const u8) -> Vec<&'static str> {
+    (0..argc)
+        .map(|i| {
+            let ptr = *argv.add(i);
+            let len = (0..).take_while(|&j| *ptr.add(j) != 0).count();
+            core::str::from_utf8(core::slice::from_raw_parts(ptr, len)).unwrap_or("")
+        })
+        .collect()
+}
+
+/// Value of the environment variable `key` of the current process
+pub fn var(key: &str) -> Result<String, Errno> {
+    let mut buf = vec![0; 64];
+    loop {
+        let len = sys_get_env(key, &mut buf)?;
+        // the value is only copied if it fits
+        if len <= buf.len() {
+            buf.truncate(len);
+            return String::from_utf8(buf).map_err(|_| Errno::EINVAL);
+        }
+        buf.resize(len, 0);
+    }
+}
+
+/// Set the environment variable `key`, inherited by processes spawned later
+pub fn set_var(key: &str, val: &str) -> Result<(), Errno> {
+    sys_set_env(key, Some(val))
+}
+
+pub fn remove_var(key: &str) -> Result<(), Errno> {
+    sys_set_env(key, None)
+}
@@ -28,7 +28,7 @@ impl Stdout {
     }
 
     pub fn write(&self, s: &str) {
-        sys_write(1, s.as_bytes());
+        let _ = sys_write(1, s.as_bytes());
     }
 }
 
@@ -38,7 +38,7 @@ impl Stderr {
     }
 
     pub fn write(&self, s: &str) {
-        sys_write(2, s.as_bytes());
+        let _ = sys_write(2, s.as_bytes());
     }
 }
 
@@ -12,6 +12,10 @@ extern crate syscall_def;
 #[macro_use]
 pub mod io;
 pub mod allocator;
+pub mod env;
+pub mod sync;
+pub mod thread;
+pub mod time;
 pub extern crate alloc;
 
 mod syscall;
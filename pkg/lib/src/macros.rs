@@ -5,8 +5,9 @@ use crate::errln;
 macro_rules! entry {
     ($fn:ident) => {
         #[export_name = "_start"]
-        pub extern "C" fn __impl_start() {
-            let ret = $fn();
+        extern "C" fn __impl_start(argc: usize, argv: *const *const u8) {
+            let args = unsafe { lib::env::args(argc, argv) };
+            let ret = $fn(&args);
             // FIXME: after syscall, add lib::sys_exit(ret);
             lib::sys_exit(ret);
             // loop {}  可以正常退出后即可删除循环
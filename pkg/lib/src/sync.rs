@@ -0,0 +1,38 @@
+use crate::*;
+use core::result::Result;
+
+// this module shadows `alloc::sync` in the crate root
+pub use alloc::sync::*;
+
+/// Kernel semaphore shared by the processes that know its `key`
+pub struct Semaphore {
+    key: u32,
+}
+
+impl Semaphore {
+    pub const fn new(key: u32) -> Self {
+        Semaphore { key }
+    }
+
+    /// Create the semaphore in the kernel, fails with `EEXIST` if it exists
+    #[inline(always)]
+    pub fn init(&self, value: usize) -> Result<(), Errno> {
+        sys_new_sem(self.key, value)
+    }
+
+    #[inline(always)]
+    pub fn remove(&self) -> Result<(), Errno> {
+        sys_remove_sem(self.key)
+    }
+
+    /// Block until the semaphore is available
+    #[inline(always)]
+    pub fn wait(&self) -> Result<(), Errno> {
+        sys_sem_wait(self.key)
+    }
+
+    #[inline(always)]
+    pub fn signal(&self) -> Result<(), Errno> {
+        sys_sem_signal(self.key)
+    }
+}
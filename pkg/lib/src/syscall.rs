@@ -1,44 +1,97 @@
-use syscall_def::Syscall;
+use alloc::vec::Vec;
+use syscall_def::ProgramArgs;
 
+pub use syscall_def::{
+    AppInfo, Errno, ProcessInfo, ProcessStatus, Signal, SysInfo, Syscall, TimeSpec, TraceRecord,
+    MAP_SHARED, PROC_THREAD, PROC_TRACED, PROT_EXEC, PROT_NONE, PROT_READ, PROT_WRITE, REBOOT_COLD,
+    REBOOT_WARM, SIG_DFL, SIG_IGN, SPAWN_TRACE, TRACE_UNFINISHED, WNOHANG,
+};
+
+/// User signal handler, called with the delivered signal
+pub type SigHandler = extern "C" fn(Signal);
+
+/// Split a raw syscall return value into the result and the error
 #[inline(always)]
-pub fn sys_write(fd: u8, buf: &[u8]) -> Option<usize> {
-    let ret = syscall!(
+fn check(ret: usize) -> Result<usize, Errno> {
+    Errno::decode(ret)
+}
+
+#[inline(always)]
+pub fn sys_write(fd: u8, buf: &[u8]) -> Result<usize, Errno> {
+    check(syscall!(
         Syscall::Write,
         fd as u64,
         buf.as_ptr() as u64,
         buf.len() as u64
-    ) as isize;
-    if ret.is_negative() {
-        None
-    } else {
-        Some(ret as usize)
-    }
+    ))
 }
 
 #[inline(always)]
-pub fn sys_read(fd: u8, buf: &mut [u8]) -> Option<usize> {
-    let ret = syscall!(
+pub fn sys_read(fd: u8, buf: &mut [u8]) -> Result<usize, Errno> {
+    check(syscall!(
         Syscall::Read,
         fd as u64,
         buf.as_ptr() as u64,
         buf.len() as u64
-    ) as isize;
-    if ret.is_negative() {
-        None
-    } else {
-        Some(ret as usize)
-    }
+    ))
 }
 
 #[inline(always)]
-pub fn sys_wait_pid(pid: u16) -> isize {
-    // FIXME: try to get the return value for process
-    //        loop until the process is finished
-    loop {
-        let ret = syscall!(Syscall::WaitPid, pid as u64) as isize;
-        if ret >= 0 {
-            return ret;
-        }
+pub fn sys_open(path: &str) -> Result<u8, Errno> {
+    check(syscall!(Syscall::Open, path.as_ptr() as u64, path.len() as u64)).map(|fd| fd as u8)
+}
+
+#[inline(always)]
+pub fn sys_close(fd: u8) -> Result<(), Errno> {
+    check(syscall!(Syscall::Close, fd as u64)).map(|_| ())
+}
+
+/// Create a pipe, returns the read fd and the write fd
+#[inline(always)]
+pub fn sys_pipe() -> Result<(u8, u8), Errno> {
+    let mut fds = [0u8; 2];
+    check(syscall!(Syscall::Pipe, fds.as_mut_ptr() as u64))?;
+    Ok((fds[0], fds[1]))
+}
+
+#[inline(always)]
+pub fn sys_dup(fd: u8) -> Result<u8, Errno> {
+    check(syscall!(Syscall::Dup, fd as u64)).map(|fd| fd as u8)
+}
+
+#[inline(always)]
+pub fn sys_dup2(fd: u8, new_fd: u8) -> Result<u8, Errno> {
+    check(syscall!(Syscall::Dup2, fd as u64, new_fd as u64)).map(|fd| fd as u8)
+}
+
+/// Wait for the child `pid` to exit, returns its exit code
+#[inline(always)]
+pub fn sys_wait_pid(pid: u16) -> Result<isize, Errno> {
+    let mut code = 0isize;
+    check(syscall!(Syscall::WaitPid, pid as u64, &mut code as *mut isize, 0))?;
+    Ok(code)
+}
+
+/// Wait for any child to exit, returns its pid and exit code
+#[inline(always)]
+pub fn sys_wait() -> Result<(u16, isize), Errno> {
+    let mut code = 0isize;
+    let pid = check(syscall!(Syscall::WaitPid, -1isize as usize, &mut code as *mut isize, 0))?;
+    Ok((pid as u16, code))
+}
+
+/// Check once whether the child `pid` has exited, returns its exit code if so
+#[inline(always)]
+pub fn sys_try_wait_pid(pid: u16) -> Result<Option<isize>, Errno> {
+    let mut code = 0isize;
+    match check(syscall!(
+        Syscall::WaitPid,
+        pid as u64,
+        &mut code as *mut isize,
+        WNOHANG
+    ))? {
+        0 => Ok(None),
+        _ => Ok(Some(code)),
     }
 }
 
@@ -47,24 +100,247 @@ pub fn sys_list_app() {
     syscall!(Syscall::ListApp);
 }
 
+/// Copy the value of the environment variable `key` to `buf` if it fits,
+/// returns its length
+#[inline(always)]
+pub fn sys_get_env(key: &str, buf: &mut [u8]) -> Result<usize, Errno> {
+    check(syscall!(
+        Syscall::GetEnv,
+        key.as_ptr(),
+        key.len(),
+        buf.as_mut_ptr(),
+        buf.len()
+    ))
+}
+
+/// Set the environment variable `key`, `None` removes it
+#[inline(always)]
+pub fn sys_set_env(key: &str, val: Option<&str>) -> Result<(), Errno> {
+    let (ptr, len) = val.map_or((core::ptr::null(), 0), |val| (val.as_ptr(), val.len()));
+    check(syscall!(Syscall::SetEnv, key.as_ptr(), key.len(), ptr, len)).map(|_| ())
+}
+
+/// Start or stop recording the syscalls of the child `pid`, `None` for the caller
+#[inline(always)]
+pub fn sys_trace(pid: Option<u16>, enable: bool) -> Result<(), Errno> {
+    check(syscall!(Syscall::Trace, pid.unwrap_or(0) as u64, enable as u64)).map(|_| ())
+}
+
+/// Move the oldest recorded syscalls into `buf`, returns how many were read
+#[inline(always)]
+pub fn sys_trace_read(buf: &mut [TraceRecord]) -> Result<usize, Errno> {
+    check(syscall!(Syscall::TraceRead, buf.as_mut_ptr(), buf.len()))
+}
+
+/// Uptime and memory statistics of the system
+#[inline(always)]
+pub fn sys_info() -> Result<SysInfo, Errno> {
+    let mut info = SysInfo::default();
+    check(syscall!(Syscall::SysInfo, &mut info as *mut SysInfo)).map(|_| info)
+}
+
+/// Fill `buf` with the first processes by pid, returns how many exist
+#[inline(always)]
+pub fn sys_proc_list(buf: &mut [ProcessInfo]) -> Result<usize, Errno> {
+    check(syscall!(Syscall::ProcList, buf.as_mut_ptr(), buf.len()))
+}
+
+/// Fill `buf` with the descriptors of the loaded apps, returns how many exist
+#[inline(always)]
+pub fn sys_app_list(buf: &mut [AppInfo]) -> Result<usize, Errno> {
+    check(syscall!(Syscall::AppList, buf.as_mut_ptr(), buf.len()))
+}
+
+/// Collect a whole list from a syscall that returns the total length
+fn fetch_all<T: Clone + Default>(
+    list: impl Fn(&mut [T]) -> Result<usize, Errno>,
+) -> Result<Vec<T>, Errno> {
+    let mut buf = Vec::new();
+    loop {
+        let total = list(&mut buf)?;
+        // entries may be added between the two calls
+        if total <= buf.len() {
+            buf.truncate(total);
+            return Ok(buf);
+        }
+        buf.resize(total + 4, T::default());
+    }
+}
+
+/// All processes, ordered by pid
+pub fn proc_list() -> Result<Vec<ProcessInfo>, Errno> {
+    fetch_all(sys_proc_list)
+}
+
+/// All apps loaded by the bootloader
+pub fn app_list() -> Result<Vec<AppInfo>, Errno> {
+    fetch_all(sys_app_list)
+}
+
 #[inline(always)]
 pub fn sys_stat() {
     syscall!(Syscall::Stat);
 }
 
+/// Map `len` bytes with `prot` and `flags` at `addr` or anywhere if `None`,
+/// returns the start of the region
+#[inline(always)]
+pub fn sys_mmap(
+    addr: Option<usize>,
+    len: usize,
+    prot: usize,
+    flags: usize,
+) -> Result<*mut u8, Errno> {
+    check(syscall!(Syscall::Mmap, addr.unwrap_or(0), len, prot, flags)).map(|addr| addr as *mut u8)
+}
+
+#[inline(always)]
+pub fn sys_munmap(addr: *mut u8, len: usize) -> Result<(), Errno> {
+    check(syscall!(Syscall::Munmap, addr, len)).map(|_| ())
+}
+
+/// Create the shared memory segment `key` of at least `size` bytes,
+/// an existing segment is reused
+#[inline(always)]
+pub fn sys_shm_get(key: u32, size: usize) -> Result<(), Errno> {
+    check(syscall!(Syscall::ShmGet, key as usize, size)).map(|_| ())
+}
+
+/// Remove the shared memory segment `key`, its memory is released once
+/// no process has it attached
+#[inline(always)]
+pub fn sys_shm_remove(key: u32) -> Result<(), Errno> {
+    check(syscall!(Syscall::ShmRemove, key as usize)).map(|_| ())
+}
+
+/// Map the shared memory segment `key` at `addr` or anywhere if `None`
+#[inline(always)]
+pub fn sys_shm_attach(key: u32, addr: Option<usize>) -> Result<*mut u8, Errno> {
+    check(syscall!(Syscall::ShmAttach, key as usize, addr.unwrap_or(0))).map(|addr| addr as *mut u8)
+}
+
+#[inline(always)]
+pub fn sys_shm_detach(addr: *mut u8) -> Result<(), Errno> {
+    check(syscall!(Syscall::ShmDetach, addr)).map(|_| ())
+}
+
+/// Move the program break to `addr`, `None` queries the current one,
+/// returns the new break
+#[inline(always)]
+pub fn sys_brk(addr: Option<usize>) -> Result<usize, Errno> {
+    check(syscall!(Syscall::Brk, addr.unwrap_or(0)))
+}
+
+/// Pack strings into a block of NUL-terminated strings
+fn str_block(strs: &[&str]) -> Vec<u8> {
+    let mut block = Vec::new();
+    for s in strs {
+        block.extend_from_slice(s.as_bytes());
+        block.push(0);
+    }
+    block
+}
+
+/// Spawn the app `path` with extra arguments `args`, `argv[0]` is the app name
+///
+/// The child gets a copy of the environment of the caller.
 #[inline(always)]
-pub fn sys_allocate(layout: &core::alloc::Layout) -> *mut u8 {
-    syscall!(Syscall::Allocate, layout as *const _) as *mut u8
+pub fn sys_spawn(path: &str, args: &[&str]) -> Result<u16, Errno> {
+    sys_spawn_flags(path, args, None, 0)
 }
 
+/// Spawn the app `path` with extra arguments `args` and environment `envs` (`KEY=VALUE`)
 #[inline(always)]
-pub fn sys_deallocate(ptr: *mut u8, layout: &core::alloc::Layout) -> usize {
-    syscall!(Syscall::Deallocate, ptr, layout as *const _)
+pub fn sys_spawn_env(path: &str, args: &[&str], envs: &[&str]) -> Result<u16, Errno> {
+    sys_spawn_flags(path, args, Some(envs), 0)
 }
 
+/// Spawn the app `path` with `SPAWN_*` flags, the caller's environment is
+/// passed on if `envs` is `None`
+pub fn sys_spawn_flags(
+    path: &str,
+    args: &[&str],
+    envs: Option<&[&str]>,
+    flags: usize,
+) -> Result<u16, Errno> {
+    let argv = str_block(args);
+    // an empty block is still non-null, it gives an empty environment
+    let envp = envs.map(str_block);
+    let program_args = ProgramArgs {
+        argv: argv.as_ptr(),
+        argv_len: argv.len(),
+        envp: envp.as_ref().map_or(core::ptr::null(), |envp| envp.as_ptr()),
+        envp_len: envp.as_ref().map_or(0, |envp| envp.len()),
+    };
+
+    check(syscall!(
+        Syscall::Spawn,
+        path.as_ptr() as u64,
+        path.len() as u64,
+        &program_args as *const _,
+        flags
+    ))
+    .map(|pid| pid as u16)
+}
+
+/// Start a thread at `entry(arg)` on `stack_top`, returns its tid
 #[inline(always)]
-pub fn sys_spawn(path: &str) -> u16 {
-    syscall!(Syscall::Spawn, path.as_ptr() as u64, path.len() as u64) as u16
+pub fn sys_thread_create(entry: usize, stack_top: usize, arg: usize) -> Result<u16, Errno> {
+    check(syscall!(Syscall::ThreadCreate, entry, stack_top, arg)).map(|tid| tid as u16)
+}
+
+/// Wait for the thread `tid` of this process to exit, returns its exit code
+#[inline(always)]
+pub fn sys_thread_join(tid: u16) -> Result<isize, Errno> {
+    let mut code = 0isize;
+    check(syscall!(Syscall::ThreadJoin, tid as u64, &mut code as *mut isize))?;
+    Ok(code)
+}
+
+/// Duplicate the current process, returns the child pid, or 0 in the child
+#[inline(always)]
+pub fn sys_fork() -> Result<u16, Errno> {
+    check(syscall!(Syscall::Fork)).map(|pid| pid as u16)
+}
+
+/// Replace the current process image with the app `path` and extra arguments `args`
+///
+/// The environment is kept. Only returns if the app cannot be executed.
+pub fn sys_exec(path: &str, args: &[&str]) -> Errno {
+    let argv = str_block(args);
+    let program_args = ProgramArgs {
+        argv: argv.as_ptr(),
+        argv_len: argv.len(),
+        envp: core::ptr::null(),
+        envp_len: 0,
+    };
+
+    let ret = syscall!(
+        Syscall::Exec,
+        path.as_ptr() as u64,
+        path.len() as u64,
+        &program_args as *const _
+    );
+    check(ret).err().unwrap_or(Errno::EINVAL)
+}
+
+/// Block for `dur`, may return early if a signal is delivered
+#[inline(always)]
+pub fn sys_sleep(dur: core::time::Duration) -> Result<(), Errno> {
+    check(syscall!(Syscall::Sleep, dur.as_nanos() as u64)).map(|_| ())
+}
+
+/// Monotonic time since boot, in nanoseconds
+#[inline(always)]
+pub fn sys_clock_get_time() -> Result<u64, Errno> {
+    check(syscall!(Syscall::ClockGetTime)).map(|ns| ns as u64)
+}
+
+/// Wall-clock time since the Unix epoch
+#[inline(always)]
+pub fn sys_time() -> Result<TimeSpec, Errno> {
+    let mut time = TimeSpec::default();
+    check(syscall!(Syscall::Time, &mut time as *mut TimeSpec)).map(|_| time)
 }
 
 #[inline(always)]
@@ -72,9 +348,140 @@ pub fn sys_get_pid() -> u16 {
     syscall!(Syscall::GetPid) as u16
 }
 
+/// Power off the machine, a nonzero `status` ends a QEMU session as failed
+///
+/// Only the console session started by the kernel may do so, otherwise
+/// this returns `EPERM`.
+#[inline(always)]
+pub fn sys_power_off(status: u8) -> Errno {
+    check(syscall!(Syscall::PowerOff, status as u64))
+        .err()
+        .unwrap_or(Errno::EINVAL)
+}
+
+/// Restart the machine, keeping the memory on a warm reset
+///
+/// Only returns on error, like `sys_power_off`.
+#[inline(always)]
+pub fn sys_reboot(warm: bool) -> Errno {
+    let kind = if warm { REBOOT_WARM } else { REBOOT_COLD };
+    check(syscall!(Syscall::Reboot, kind as u64))
+        .err()
+        .unwrap_or(Errno::EINVAL)
+}
+
 #[inline(always)]
 pub fn sys_exit(code: isize) -> ! {
     syscall!(Syscall::Exit, code as u64);
-    // unreachable!("This process should be terminated by now.")
-    loop{}
+    // the kernel does not return from `Exit`
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[inline(always)]
+pub fn sys_new_sem(key: u32, value: usize) -> Result<(), Errno> {
+    check(syscall!(Syscall::Sem, 0, key as usize, value)).map(|_| ())
+}
+
+#[inline(always)]
+pub fn sys_remove_sem(key: u32) -> Result<(), Errno> {
+    check(syscall!(Syscall::Sem, 1, key as usize)).map(|_| ())
+}
+
+#[inline(always)]
+pub fn sys_sem_signal(key: u32) -> Result<(), Errno> {
+    check(syscall!(Syscall::Sem, 2, key as usize)).map(|_| ())
+}
+
+#[inline(always)]
+pub fn sys_sem_wait(key: u32) -> Result<(), Errno> {
+    check(syscall!(Syscall::Sem, 3, key as usize)).map(|_| ())
+}
+
+/// Send `sig` to process `pid`
+#[inline(always)]
+pub fn sys_kill(pid: u16, sig: Signal) -> Result<(), Errno> {
+    check(syscall!(Syscall::Kill, pid as u64, sig as u64)).map(|_| ())
+}
+
+/// Send `sig` to every process of the group `pgid`
+#[inline(always)]
+pub fn sys_kill_group(pgid: u16, sig: Signal) -> Result<(), Errno> {
+    check(syscall!(Syscall::Kill, -(pgid as i64) as u64, sig as u64)).map(|_| ())
+}
+
+/// Move `pid` to the group `pgid`, `None` for the caller and a group of its own
+#[inline(always)]
+pub fn sys_set_pgid(pid: Option<u16>, pgid: Option<u16>) -> Result<(), Errno> {
+    check(syscall!(
+        Syscall::SetPgid,
+        pid.unwrap_or(0) as u64,
+        pgid.unwrap_or(0) as u64
+    ))
+    .map(|_| ())
+}
+
+/// The process group of `pid`, `None` for the caller
+#[inline(always)]
+pub fn sys_get_pgid(pid: Option<u16>) -> Result<u16, Errno> {
+    check(syscall!(Syscall::GetPgid, pid.unwrap_or(0) as u64)).map(|pgid| pgid as u16)
+}
+
+/// Start a new session led by the caller, returns its id
+#[inline(always)]
+pub fn sys_set_sid() -> Result<u16, Errno> {
+    check(syscall!(Syscall::SetSid)).map(|sid| sid as u16)
+}
+
+/// The session of `pid`, `None` for the caller
+#[inline(always)]
+pub fn sys_get_sid(pid: Option<u16>) -> Result<u16, Errno> {
+    check(syscall!(Syscall::GetSid, pid.unwrap_or(0) as u64)).map(|sid| sid as u16)
+}
+
+/// Give the console to the group `pgid`
+#[inline(always)]
+pub fn sys_tc_set_pgrp(pgid: u16) -> Result<(), Errno> {
+    check(syscall!(Syscall::TcSetPgrp, pgid as u64)).map(|_| ())
+}
+
+/// The foreground group of the console, if any
+#[inline(always)]
+pub fn sys_tc_get_pgrp() -> Option<u16> {
+    match syscall!(Syscall::TcGetPgrp) {
+        0 => None,
+        pgid => Some(pgid as u16),
+    }
+}
+
+/// Set the raw handler of `sig`, `SIG_DFL` and `SIG_IGN` are accepted,
+/// returns the previous handler
+#[inline(always)]
+pub fn sys_sigaction(sig: Signal, handler: usize) -> Result<usize, Errno> {
+    let trampoline: extern "C" fn(Signal, SigHandler) -> ! = sig_trampoline;
+    check(syscall!(
+        Syscall::SigAction,
+        sig as u64,
+        handler as u64,
+        trampoline as usize
+    ))
+}
+
+/// Call `handler` when `sig` is delivered
+#[inline(always)]
+pub fn sys_signal(sig: Signal, handler: SigHandler) -> Result<usize, Errno> {
+    sys_sigaction(sig, handler as usize)
+}
+
+#[inline(always)]
+pub fn sys_sigreturn() -> ! {
+    syscall!(Syscall::SigReturn);
+    unreachable!("Not in a signal handler.")
+}
+
+/// The kernel enters signal handlers here, with the signal and the handler
+extern "C" fn sig_trampoline(sig: Signal, handler: SigHandler) -> ! {
+    handler(sig);
+    sys_sigreturn()
 }
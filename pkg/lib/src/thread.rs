@@ -0,0 +1,61 @@
+use alloc::boxed::Box;
+
+use crate::*;
+use core::result::Result;
+
+/// Size of the stack of a new thread, populated on demand
+pub const THREAD_STACK_SIZE: usize = 64 * 1024;
+
+type ThreadMain = Box<dyn FnOnce() + Send + 'static>;
+
+/// Handle to wait for a thread
+pub struct JoinHandle {
+    tid: u16,
+    stack: *mut u8,
+}
+
+impl JoinHandle {
+    pub fn tid(&self) -> u16 {
+        self.tid
+    }
+
+    /// Wait for the thread to exit, returns its exit code
+    pub fn join(self) -> Result<isize, Errno> {
+        let ret = sys_thread_join(self.tid)?;
+        sys_munmap(self.stack, THREAD_STACK_SIZE)?;
+        Ok(ret)
+    }
+}
+
+/// The kernel starts new threads here, with the boxed closure as argument
+extern "C" fn thread_start(arg: usize) -> ! {
+    let main = unsafe { Box::from_raw(arg as *mut ThreadMain) };
+    main();
+    sys_exit(0)
+}
+
+/// Run `f` in a new thread of the current process
+///
+/// Fails if the stack cannot be allocated, or the thread cannot be created.
+pub fn spawn<F>(f: F) -> Result<JoinHandle, Errno>
+where
+    F: FnOnce() + Send + 'static,
+{
+    let stack = sys_mmap(None, THREAD_STACK_SIZE, PROT_READ | PROT_WRITE, 0)?;
+
+    // aligned as if `thread_start` was called
+    let stack_top = stack as usize + THREAD_STACK_SIZE - 8;
+
+    let main: Box<ThreadMain> = Box::new(Box::new(f));
+    let arg = Box::into_raw(main) as usize;
+
+    let entry: extern "C" fn(usize) -> ! = thread_start;
+    match sys_thread_create(entry as usize, stack_top, arg) {
+        Ok(tid) => Ok(JoinHandle { tid, stack }),
+        Err(errno) => {
+            drop(unsafe { Box::from_raw(arg as *mut ThreadMain) });
+            let _ = sys_munmap(stack, THREAD_STACK_SIZE);
+            Err(errno)
+        }
+    }
+}
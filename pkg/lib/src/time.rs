@@ -0,0 +1,128 @@
+use core::ops::{Add, Sub};
+use core::time::Duration;
+
+use crate::*;
+
+/// A point of the monotonic clock
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(u64);
+
+impl Instant {
+    pub fn now() -> Self {
+        Self(sys_clock_get_time().expect("Monotonic clock unavailable"))
+    }
+
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        Duration::from_nanos(self.0.saturating_sub(earlier.0))
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Instant::now().duration_since(*self)
+    }
+
+    pub fn as_nanos(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, rhs: Duration) -> Instant {
+        Instant(self.0.saturating_add(rhs.as_nanos() as u64))
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, rhs: Instant) -> Duration {
+        self.duration_since(rhs)
+    }
+}
+
+/// A point of the wall clock, as time since the Unix epoch
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SystemTime(Duration);
+
+impl SystemTime {
+    pub const UNIX_EPOCH: SystemTime = SystemTime(Duration::ZERO);
+
+    /// The epoch if the kernel cannot tell the time
+    pub fn now() -> Self {
+        sys_time()
+            .map(|time| Self(Duration::new(time.sec, time.nsec as u32)))
+            .unwrap_or(Self::UNIX_EPOCH)
+    }
+
+    pub fn since_epoch(&self) -> Duration {
+        self.0
+    }
+
+    /// The calendar date and time in UTC
+    pub fn date_time(&self) -> DateTime {
+        let secs = self.0.as_secs();
+        let (year, month, day) = civil_from_days((secs / 86400) as i64);
+        let secs = secs % 86400;
+
+        DateTime {
+            year: year as u16,
+            month,
+            day,
+            hour: (secs / 3600) as u8,
+            minute: (secs / 60 % 60) as u8,
+            second: (secs % 60) as u8,
+        }
+    }
+}
+
+/// A date of the proleptic Gregorian calendar and a time of day
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl core::fmt::Display for DateTime {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// Year, month and day of the `days`th day since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    // years start in March, so the leap day is the last day of the year
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Sleep for at least `dur`
+pub fn sleep(dur: Duration) {
+    let deadline = Instant::now() + dur;
+
+    // the process may be woken up early to handle a signal
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        let _ = sys_sleep(deadline.duration_since(now));
+    }
+}
@@ -1,6 +1,6 @@
 #![no_std]
 
-use num_enum::FromPrimitive;
+use num_enum::{FromPrimitive, TryFromPrimitive};
 
 pub mod macros;
 
@@ -9,18 +9,409 @@ pub mod macros;
 pub enum Syscall {
     Read = 0,
     Write = 1,
+    Open = 2,
+    Close = 3,
+
+    Mmap = 9,
+    Munmap = 11,
+
+    Brk = 12,
+    SigAction = 13,
+    SigReturn = 15,
+
+    Pipe = 22,
+
+    ShmGet = 29,
+    ShmAttach = 30,
+    ShmRemove = 31,
+
+    Dup = 32,
+    Dup2 = 33,
+
+    Sleep = 35,
 
     GetPid = 39,
-    
+
+    ThreadCreate = 56,
+    Fork = 57,
+    Exec = 58,
     Spawn = 59,
     Exit = 60,
     WaitPid = 61,
+    Kill = 62,
+
+    Sem = 66,
+    ShmDetach = 67,
 
+    SetPgid = 109,
+    SetSid = 112,
+    GetPgid = 121,
+    GetSid = 124,
+
+    Time = 201,
+    ClockGetTime = 228,
+
+    Reboot = 65519,
+    PowerOff = 65520,
+    SysInfo = 65521,
+    TcSetPgrp = 65522,
+    TcGetPgrp = 65523,
+    GetEnv = 65524,
+    SetEnv = 65525,
+    AppList = 65526,
+    ProcList = 65527,
+    TraceRead = 65528,
+    Trace = 65529,
+    ThreadJoin = 65530,
     ListApp = 65531,
     Stat = 65532,
-    Allocate = 65533,
-    Deallocate = 65534,
 
     #[num_enum(default)]
     Unknown = 65535,
 }
+
+impl Syscall {
+    /// Number of arguments used by the syscall, see the kernel dispatcher
+    pub fn arg_count(&self) -> usize {
+        match self {
+            Self::GetPid
+            | Self::Fork
+            | Self::ClockGetTime
+            | Self::SigReturn
+            | Self::Stat
+            | Self::ListApp
+            | Self::SetSid
+            | Self::TcGetPgrp => 0,
+            Self::Close
+            | Self::Pipe
+            | Self::Dup
+            | Self::Brk
+            | Self::ShmDetach
+            | Self::ShmRemove
+            | Self::Exit
+            | Self::Sleep
+            | Self::GetPgid
+            | Self::GetSid
+            | Self::TcSetPgrp
+            | Self::SysInfo
+            | Self::Time
+            | Self::PowerOff
+            | Self::Reboot => 1,
+            Self::Open
+            | Self::Dup2
+            | Self::Munmap
+            | Self::ShmGet
+            | Self::ShmAttach
+            | Self::ThreadJoin
+            | Self::WaitPid
+            | Self::Kill
+            | Self::SetPgid
+            | Self::Trace
+            | Self::TraceRead
+            | Self::ProcList
+            | Self::AppList => 2,
+            Self::Read
+            | Self::Write
+            | Self::ThreadCreate
+            | Self::Exec
+            | Self::SigAction
+            | Self::Sem => 3,
+            Self::Mmap | Self::Spawn | Self::GetEnv | Self::SetEnv => 4,
+            Self::Unknown => 6,
+        }
+    }
+}
+
+/// Error of a failed syscall, returned negated in `rax`
+///
+/// Return values in `[-MAX_ERRNO, -1]` are errors, anything else is a result.
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
+pub enum Errno {
+    /// Operation not permitted
+    EPERM = 1,
+    /// No such file, app or object
+    ENOENT = 2,
+    /// No such process
+    ESRCH = 3,
+    /// Interrupted before it could complete
+    EINTR = 4,
+    /// Bad file descriptor
+    EBADF = 9,
+    /// No child process to wait for
+    ECHILD = 10,
+    /// Try again
+    EAGAIN = 11,
+    /// Out of memory
+    ENOMEM = 12,
+    /// Bad address
+    EFAULT = 14,
+    /// Object already exists
+    EEXIST = 17,
+    /// Invalid argument
+    EINVAL = 22,
+    /// Too many open files
+    EMFILE = 24,
+    /// Broken pipe
+    EPIPE = 32,
+    /// Unknown syscall
+    ENOSYS = 38,
+}
+
+/// Largest error number, see `Errno`
+pub const MAX_ERRNO: usize = 4095;
+
+impl Errno {
+    /// Raw return value of a syscall failing with this error
+    #[inline]
+    pub fn as_ret(self) -> usize {
+        (self as usize).wrapping_neg()
+    }
+
+    /// Encode the result of a syscall into its raw return value
+    #[inline]
+    pub fn encode(ret: Result<usize, Errno>) -> usize {
+        match ret {
+            Ok(value) => value,
+            Err(errno) => errno.as_ret(),
+        }
+    }
+
+    /// Decode the raw return value of a syscall
+    #[inline]
+    pub fn decode(ret: usize) -> Result<usize, Errno> {
+        if ret.wrapping_neg() <= MAX_ERRNO && ret != 0 {
+            Err(Errno::try_from(ret.wrapping_neg()).unwrap_or(Errno::EINVAL))
+        } else {
+            Ok(ret)
+        }
+    }
+}
+
+impl core::fmt::Display for Errno {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let desc = match self {
+            Errno::EPERM => "operation not permitted",
+            Errno::ENOENT => "no such file or object",
+            Errno::ESRCH => "no such process",
+            Errno::EINTR => "interrupted",
+            Errno::EBADF => "bad file descriptor",
+            Errno::ECHILD => "no child process",
+            Errno::EAGAIN => "try again",
+            Errno::ENOMEM => "out of memory",
+            Errno::EFAULT => "bad address",
+            Errno::EEXIST => "already exists",
+            Errno::EINVAL => "invalid argument",
+            Errno::EMFILE => "too many open files",
+            Errno::EPIPE => "broken pipe",
+            Errno::ENOSYS => "unknown syscall",
+        };
+        write!(f, "{:?}: {}", self, desc)
+    }
+}
+
+/// Argument and environment blocks passed to a new program image.
+///
+/// Each block holds NUL-terminated strings back to back,
+/// a null `envp` passes on the environment of the caller.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct ProgramArgs {
+    pub argv: *const u8,
+    pub argv_len: usize,
+    pub envp: *const u8,
+    pub envp_len: usize,
+}
+
+/// Signals that can be sent to a process with `Syscall::Kill`
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
+pub enum Signal {
+    SIGINT = 2,
+    SIGKILL = 9,
+    SIGSEGV = 11,
+    SIGALRM = 14,
+    SIGTERM = 15,
+    SIGCHLD = 17,
+    /// Resume a stopped process
+    SIGCONT = 18,
+    /// Stop the process, cannot be handled
+    SIGSTOP = 19,
+    /// Stop request from the console
+    SIGTSTP = 20,
+}
+
+/// Handler value for the default action of a signal
+pub const SIG_DFL: usize = 0;
+/// Handler value to ignore a signal
+pub const SIG_IGN: usize = 1;
+
+/// Protection bits and flags of `Syscall::Mmap`, passed as separate arguments
+pub const PROT_NONE: usize = 0;
+pub const PROT_READ: usize = 0x1;
+pub const PROT_WRITE: usize = 0x2;
+pub const PROT_EXEC: usize = 0x4;
+/// The region is shared with forked children instead of copied
+pub const MAP_SHARED: usize = 0x100;
+
+/// Flag of `Syscall::WaitPid` to return 0 instead of blocking while the
+/// children are running
+pub const WNOHANG: usize = 0x1;
+
+/// Flag of `Syscall::Spawn` to record the syscalls of the new process,
+/// see `Syscall::Trace`
+pub const SPAWN_TRACE: usize = 0x1;
+
+/// The process was switched out before the syscall returned, `ret` is unset
+pub const TRACE_UNFINISHED: u16 = 0x1;
+
+/// A syscall of a traced process, read back with `Syscall::TraceRead`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TraceRecord {
+    pub pid: u16,
+    /// See `TRACE_UNFINISHED`
+    pub flags: u16,
+    /// Keeps the record free of padding
+    pub reserved: u32,
+    pub syscall: usize,
+    pub args: [usize; 6],
+    /// Raw return value, see `Errno::decode`
+    pub ret: usize,
+    /// Timer ticks since boot when the syscall was entered
+    pub tick: u64,
+}
+
+/// Bytes kept of a process name in `ProcessInfo`, longer names are truncated
+pub const PROC_NAME_LEN: usize = 16;
+
+/// The process is a thread sharing the address space of its parent
+pub const PROC_THREAD: u8 = 0x1;
+/// The syscalls of the process are recorded, see `Syscall::Trace`
+pub const PROC_TRACED: u8 = 0x2;
+
+/// Scheduling state of a process in `ProcessInfo`
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
+pub enum ProcessStatus {
+    Running = 0,
+    Ready = 1,
+    Blocked = 2,
+    /// Exited, kept until its exit code is collected
+    Dead = 3,
+}
+
+/// A process listed by `Syscall::ProcList`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProcessInfo {
+    pub pid: u16,
+    /// 0 for processes without a parent
+    pub ppid: u16,
+    /// See `ProcessStatus`
+    pub status: u8,
+    /// `PROC_*` bits
+    pub flags: u8,
+    pub name_len: u8,
+    /// Keeps the record free of padding
+    pub reserved: u8,
+    pub name: [u8; PROC_NAME_LEN],
+    /// Timer ticks the process has run for
+    pub ticks: u64,
+    /// Bytes of code, stack, heap and mapped regions in its address space
+    pub memory: u64,
+    /// Only meaningful once the process is dead
+    pub exit_code: isize,
+}
+
+impl ProcessInfo {
+    pub fn name(&self) -> &str {
+        let len = (self.name_len as usize).min(PROC_NAME_LEN);
+        core::str::from_utf8(&self.name[..len]).unwrap_or("?")
+    }
+
+    pub fn status(&self) -> Option<ProcessStatus> {
+        ProcessStatus::try_from(self.status).ok()
+    }
+}
+
+/// Max bytes of an app name
+pub const APP_NAME_LEN: usize = 16;
+
+/// An app loaded by the bootloader, listed by `Syscall::AppList`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AppInfo {
+    pub name: [u8; APP_NAME_LEN],
+    pub name_len: u8,
+    /// Keeps the record free of padding
+    pub reserved: [u8; 3],
+    /// Number of loadable segments
+    pub segments: u32,
+    /// Bytes of the ELF file
+    pub elf_size: u64,
+    pub entry: u64,
+    /// Bytes of memory taken by the loadable segments
+    pub mem_size: u64,
+}
+
+impl AppInfo {
+    pub fn name(&self) -> &str {
+        let len = (self.name_len as usize).min(APP_NAME_LEN);
+        core::str::from_utf8(&self.name[..len]).unwrap_or("?")
+    }
+}
+
+/// `Syscall::Reboot` by a full power cycle
+pub const REBOOT_COLD: usize = 0;
+/// `Syscall::Reboot` by a processor reset, keeping the memory
+pub const REBOOT_WARM: usize = 1;
+
+/// Max bytes of the kernel version
+pub const VERSION_LEN: usize = 16;
+
+/// State of the whole system, returned by `Syscall::SysInfo`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SysInfo {
+    /// Version of the kernel crate
+    pub version: [u8; VERSION_LEN],
+    pub version_len: u8,
+    /// Keeps the record free of padding
+    pub reserved: u8,
+    /// Processes known to the kernel, including dead ones not yet reaped
+    pub processes: u16,
+    /// Processors running a process
+    pub cpus: u32,
+    /// Timer ticks since boot
+    pub uptime: u64,
+    /// Nanoseconds of a timer tick
+    pub tick_ns: u64,
+    /// Physical frames of 4 KiB usable by the kernel
+    pub frames_total: u64,
+    pub frames_used: u64,
+    /// Bytes of the kernel heap
+    pub heap_total: u64,
+    pub heap_used: u64,
+}
+
+impl SysInfo {
+    pub fn version(&self) -> &str {
+        let len = (self.version_len as usize).min(VERSION_LEN);
+        core::str::from_utf8(&self.version[..len]).unwrap_or("?")
+    }
+
+    /// Time since boot in nanoseconds
+    pub fn uptime_ns(&self) -> u64 {
+        self.uptime * self.tick_ns
+    }
+}
+
+/// Wall-clock time since the Unix epoch, returned by `Syscall::Time`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeSpec {
+    pub sec: u64,
+    /// Always below one second
+    pub nsec: u64,
+}
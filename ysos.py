@@ -83,7 +83,8 @@ def qemu(output: str = '-nographic', memory: str = '96M', debug: bool = False, i
         raise Exception('qemu-system-x86_64 not found in PATH')
 
     qemu_args = [qemu_exe, '-bios', args.bios, '-net', 'none', *output.split(),
-                 '-m', memory, '-drive', 'format=raw,file=fat:rw:esp']
+                 '-m', memory, '-drive', 'format=raw,file=fat:rw:esp',
+                 '-device', 'isa-debug-exit,iobase=0xf4,iosize=0x04']
 
     if debug:
         qemu_args += ['-s', '-S']